@@ -7,12 +7,21 @@ export function getSteamPath(): string
 export interface AppInfo {
   appid: number
   gamename: string
+  /** Process the appid was found in. Not available on Windows, where it is read from the registry */
+  pid?: number
+  ppid?: number
+  /** Unix timestamp */
+  startTime?: number
+  /** Most likely the game being played. Always the first entry */
+  foreground: boolean
 }
 export function getAppInfo(): Array<AppInfo>
 export function pressKeysWin32(keys: Array<number>): void
 export function pressKeysLinux(keys: Array<string>): void
 export function getHqIcon(appid: number): string
-export function depsInstalled(lib: string): string
+/** Succeeds when everything `lib` needs is installed, otherwise the error names the missing dependency */
+export function depsInstalled(lib: string): void
+/** Returns `sspath` once the image is saved */
 export function hdrScreenshot(monitorId: number, sspath: string, area?: [number, number, number, number] | undefined | null): string
 export function getFocusedWinPath(): string
 export interface WinBounds {
@@ -43,7 +52,321 @@ export interface JsMonitorInfo {
   edid?: Array<number>
 }
 export function findElectronDisplay(id: number): JsMonitorInfo | null
+export interface SteamInstall {
+  /** Canonical (symlink-resolved) path to the Steam root directory */
+  path: string
+  /** Candidate location the install was found through (e.g. `~/.steam/steam`) */
+  source: string
+  /** `native`, `flatpak`, `snap` or `registry` */
+  kind: string
+  active: boolean
+}
+export function getSteamInstalls(): Array<SteamInstall>
+/** Errors thrown to JS carry one of these as their `code` property, so callers can branch on `err.code` instead of matching messages */
+export const enum ErrorCode {
+  SteamNotFound = 'STEAM_NOT_FOUND',
+  AppNotFound = 'APP_NOT_FOUND',
+  FileNotFound = 'FILE_NOT_FOUND',
+  ReadFailed = 'READ_FAILED',
+  ParseFailed = 'PARSE_FAILED',
+  KeyNotFound = 'KEY_NOT_FOUND',
+  InvalidArgument = 'INVALID_ARGUMENT',
+  WatchFailed = 'WATCH_FAILED',
+  DecodeFailed = 'DECODE_FAILED',
+  EncodeFailed = 'ENCODE_FAILED',
+  MonitorNotFound = 'MONITOR_NOT_FOUND',
+  CaptureFailed = 'CAPTURE_FAILED',
+  WriteFailed = 'WRITE_FAILED',
+  WindowNotFound = 'WINDOW_NOT_FOUND',
+  IconNotFound = 'ICON_NOT_FOUND',
+  DependencyMissing = 'DEPENDENCY_MISSING',
+  Cancelled = 'CANCELLED'
+}
+export function queryVdf(path: string, keyPath: string): any
+export interface LibraryFolder {
+  index: number
+  path: string
+  label: string
+  contentid: string
+  totalsize: number
+  /** Installed appids mapped to their size on disk in bytes */
+  apps: Record<string, number>
+}
+export function getLibraryFolders(): Array<LibraryFolder>
+export interface AppManifest {
+  appid: number
+  name: string
+  installdir: string
+  /** Absolute path of `steamapps/common/<installdir>` in the owning library */
+  installPath: string
+  stateFlags: number
+  /** Names of every `EAppState` bit set in `state_flags` */
+  states: Array<string>
+  installed: boolean
+  updateRequired: boolean
+  updating: boolean
+  buildid: number
+  /** Unix timestamp */
+  lastUpdated: number
+  sizeOnDisk: number
+  /** SteamID64 */
+  lastOwner: string
+  libraryIndex: number
+  libraryPath: string
+}
+export function getAppManifest(appid: number): AppManifest
+export function getInstalledApps(): Array<AppManifest>
+export interface AppInfoEntry {
+  appid: number
+  name: string
+  type: string
+  /** Hash of `steam/games/<clienticon>.ico` in the Steam root */
+  clienticon: string
+  /** Parent app of a DLC, tool or demo */
+  parent?: number
+  lastUpdated: number
+  changeNumber: number
+}
+export function getAppInfoEntry(appid: number): AppInfoEntry
+export interface AchievementSchema {
+  apiname: string
+  /** Display names keyed by Steam language name (`english`, `german`...) */
+  displayName: Record<string, string>
+  description: Record<string, string>
+  /** Hash of the icon under the CDN `<appid>/` path (`<hash>.jpg`) */
+  icon: string
+  iconGray: string
+  hidden: boolean
+  /** Id of the stat whose bitfield stores this achievement */
+  statId: number
+  bit: number
+}
+export interface StatSchema {
+  id: number
+  name: string
+  /** `int`, `float` or `avgrate` */
+  type: string
+  displayName: string
+}
+export interface GameStatsSchema {
+  appid: number
+  gamename: string
+  version: number
+  stats: Array<StatSchema>
+  achievements: Array<AchievementSchema>
+}
+export function getAchievementSchema(appid: number): GameStatsSchema
+export interface AchievementState {
+  apiname: string
+  unlocked: boolean
+  /** Unix timestamp, or 0 if locked */
+  unlocktime: number
+}
+export interface StatValue {
+  id: number
+  name: string
+  value: number
+}
+export interface UserGameStats {
+  appid: number
+  accountid: number
+  achievements: Array<AchievementState>
+  stats: Array<StatValue>
+}
+export function getAchievementStates(appid: number, accountid: number): UserGameStats
+export interface UnlockEvent {
+  appid: number
+  apiname: string
+  unlocktime: number
+}
+export interface SteamUser {
+  /** SteamID64. 64-bit ids are strings throughout, as they exceed `Number.MAX_SAFE_INTEGER` */
+  steamid64: string
+  /** 32-bit account id, as used in `userdata/<accountid>` and stats filenames */
+  accountid: number
+  accountName: string
+  personaName: string
+  mostRecent: boolean
+  wantsOfflineMode: boolean
+  /** Unix timestamp of the last login */
+  timestamp: number
+}
+export interface SteamId {
+  steamid64: string
+  accountid: number
+  /** `[U:1:<accountid>]` */
+  steam3: string
+}
+export function getSteamUsers(): Array<SteamUser>
+export function convertSteamId(id: string): SteamId
+export function getSteamRegistryValue(path: string): string | null
+export interface Shortcut {
+  /** 32-bit shortcut id, as reported in `AppId=` and used for `userdata/<accountid>/config/grid` artwork */
+  appid: number
+  /** 64-bit game id, as used in `steam://rungameid/<gameid>` */
+  gameid: string
+  appName: string
+  exe: string
+  startDir: string
+  icon: string
+  launchOptions: string
+  tags: Array<string>
+  isHidden: boolean
+  /** Unix timestamp */
+  lastPlayTime: number
+}
+export function getShortcuts(accountid: number): Array<Shortcut>
+export interface UserAppConfig {
+  appid: number
+  /** Unix timestamp */
+  lastPlayed: number
+  /** Total minutes played */
+  playtime: number
+  /** Minutes played in the last two weeks */
+  playtime2wks: number
+  /** Steam only stores `OverlayAppEnable` once changed, and the overlay is enabled by default */
+  overlayAppEnable: boolean
+  launchOptions: string
+}
+export function getUserAppConfig(accountid: number, appid: number): UserAppConfig
+export interface Artwork {
+  /** `icon`, `header`, `library_600x900`, `library_600x900_2x`, `library_hero`, `library_hero_blur`, `library_header` or `logo` */
+  kind: string
+  /** `librarycache`, `hicolor` or `clienticon` */
+  source: string
+  path: string
+  width: number
+  height: number
+}
+export interface AppArtwork {
+  appid: number
+  /** Path of the largest icon found, or an empty string */
+  icon: string
+  images: Array<Artwork>
+}
+export function getAppArtwork(appid: number): AppArtwork
+/**
+ * Accepts the paths returned by `getHqIcon`/`getAppArtwork`, including `DisplayIcon` values with an icon index.
+ * `size` is at most 1024
+ */
+export function getIconImage(path: string, size: number): Buffer
+export interface CompatInfo {
+  appid: number
+  /** Tool name from `CompatToolMapping`, e.g. `proton_experimental` or `GE-Proton9-7`. Empty for native games */
+  toolName: string
+  toolDisplayName: string
+  /** Empty when the tool is not installed */
+  toolPath: string
+  /** No tool is set for the game itself, so Steam's global default applies */
+  isDefaultTool: boolean
+  /** Wine prefix, `steamapps/compatdata/<appid>/pfx` in the game's library folder */
+  prefixPath: string
+  prefixExists: boolean
+}
+export function getCompatInfo(appid: number): CompatInfo
+export interface SessionInfo {
+  /** Running inside gamescope, either Gaming Mode on a Deck or a nested `gamescope` session */
+  gamescope: boolean
+  steamDeck: boolean
+  /** DMI product name, e.g. `Jupiter` or `Galileo` on a Steam Deck */
+  productName: string
+  /** `x11`, `xwayland`, `wayland`, `windows` or `unknown` */
+  displayServer: string
+  /** `XDG_CURRENT_DESKTOP` */
+  desktop: string
+}
+export function getSessionInfo(): SessionInfo
+export interface EncodeOptions {
+  /** `png` (default), `jpeg` or `webp` */
+  format?: string
+  /** 1-100, used by JPEG and lossy WebP. Defaults to 90 */
+  quality?: number
+  /** Encodes WebP losslessly. PNG is always lossless and JPEG never is */
+  lossless?: boolean
+}
+export interface ScreenshotBuffer {
+  data: Buffer
+  /** `png`, `jpeg` or `webp` */
+  format: string
+  width: number
+  height: number
+  /** Electron display id of the captured monitor, which differs from the requested one after a fallback to the primary monitor */
+  monitorId: number
+}
+/** Captures to memory, avoiding the disk round-trip of `hdrScreenshot` */
+export function hdrScreenshotBuffer(monitorId: number, area?: [number, number, number, number] | undefined | null, options?: EncodeOptions | undefined | null): ScreenshotBuffer
+/**
+ * Captures and encodes on a libuv worker thread. Aborting `signal` rejects with code `CANCELLED` once the worker returns,
+ * and a capture already in progress is discarded
+ */
+export function hdrScreenshotAsync(monitorId: number, area?: [number, number, number, number] | undefined | null, options?: EncodeOptions | undefined | null, signal?: AbortSignal | undefined | null): Promise<ScreenshotBuffer>
+/** Every field given must match */
+export interface WindowQuery {
+  /** Exact window title */
+  title?: string
+  pid?: number
+  /** Executable of the process owning the window */
+  processPath?: string
+}
+export interface WindowCapture {
+  data: Buffer
+  /** `png`, `jpeg` or `webp` */
+  format: string
+  width: number
+  height: number
+  /** Desktop position of the captured area */
+  x: number
+  y: number
+  /** `xcomposite` or `printwindow` (the window's own contents, even when occluded) or `crop` (the desktop area the window covers) */
+  method: string
+}
+export function captureWindow(query: WindowQuery, options?: EncodeOptions | undefined | null): WindowCapture
+export interface Caption {
+  /** Drawn top to bottom, e.g. game name, achievement title and unlock date */
+  lines: Array<string>
+  /** Font size in pixels, at most 256. Defaults to 18 */
+  size?: number
+  /** `#RRGGBB` or `#RRGGBBAA`. Defaults to white */
+  color?: string
+  /** `#RRGGBB` or `#RRGGBBAA`. Defaults to translucent black */
+  background?: string
+}
+export interface CompositeOptions {
+  /** `topleft`, `top`, `topright`, `left`, `center`, `right`, `bottomleft`, `bottom`, `bottomright` (default) or `custom` */
+  anchor?: string
+  /** Margin from the anchored edge, or the absolute position when `anchor` is `custom` */
+  offsetX?: number
+  offsetY?: number
+  /** Applied to the notification before it is placed, which must still fit inside the screenshot. Defaults to 1 */
+  scale?: number
+  /** 0-1. Defaults to 1 */
+  opacity?: number
+  /** Drawn below the notification, or above it when there is no room below */
+  caption?: Caption
+  output?: EncodeOptions
+  /** Also writes the encoded image here */
+  path?: string
+}
+export interface CompositeResult {
+  data: Buffer
+  /** `png`, `jpeg` or `webp` */
+  format: string
+  width: number
+  height: number
+}
+/** Both images may be a path or an encoded buffer, e.g. from `hdrScreenshotBuffer` */
+export function compositeNotification(screenshot: string | Buffer, notification: string | Buffer, options?: CompositeOptions | undefined | null): CompositeResult
 export namespace log {
   export function initLogger(appData: string): string
   export function testPanic(): void
 }
+/**
+ * Watches `appcache/stats` and calls back with `{ appid, apiname, unlocktime }` for every new unlock.
+ * Uses inotify on Linux, otherwise polls file modification times every `intervalMs`
+ */
+export class AchievementWatcher {
+  constructor(accountid?: number | undefined | null, intervalMs?: number | undefined | null)
+  start(callback: (event: UnlockEvent) => void): void
+  stop(): void
+  get running(): boolean
+}
@@ -295,7 +295,7 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { getSteamPath, getAppInfo, pressKeysWin32, pressKeysLinux, getHqIcon, depsInstalled, hdrScreenshot, getFocusedWinPath, getWindowBounds, getAllDisplays, findElectronDisplay, log } = nativeBinding
+const { getSteamPath, getAppInfo, pressKeysWin32, pressKeysLinux, getHqIcon, depsInstalled, hdrScreenshot, getFocusedWinPath, getWindowBounds, getAllDisplays, findElectronDisplay, getSteamInstalls, ErrorCode, queryVdf, getLibraryFolders, getAppManifest, getInstalledApps, getAppInfoEntry, getAchievementSchema, getAchievementStates, AchievementWatcher, getSteamUsers, convertSteamId, getSteamRegistryValue, getShortcuts, getUserAppConfig, getAppArtwork, getIconImage, getCompatInfo, getSessionInfo, hdrScreenshotBuffer, hdrScreenshotAsync, captureWindow, compositeNotification, log } = nativeBinding
 
 module.exports.getSteamPath = getSteamPath
 module.exports.getAppInfo = getAppInfo
@@ -308,4 +308,27 @@ module.exports.getFocusedWinPath = getFocusedWinPath
 module.exports.getWindowBounds = getWindowBounds
 module.exports.getAllDisplays = getAllDisplays
 module.exports.findElectronDisplay = findElectronDisplay
+module.exports.getSteamInstalls = getSteamInstalls
+module.exports.ErrorCode = ErrorCode
+module.exports.queryVdf = queryVdf
+module.exports.getLibraryFolders = getLibraryFolders
+module.exports.getAppManifest = getAppManifest
+module.exports.getInstalledApps = getInstalledApps
+module.exports.getAppInfoEntry = getAppInfoEntry
+module.exports.getAchievementSchema = getAchievementSchema
+module.exports.getAchievementStates = getAchievementStates
+module.exports.AchievementWatcher = AchievementWatcher
+module.exports.getSteamUsers = getSteamUsers
+module.exports.convertSteamId = convertSteamId
+module.exports.getSteamRegistryValue = getSteamRegistryValue
+module.exports.getShortcuts = getShortcuts
+module.exports.getUserAppConfig = getUserAppConfig
+module.exports.getAppArtwork = getAppArtwork
+module.exports.getIconImage = getIconImage
+module.exports.getCompatInfo = getCompatInfo
+module.exports.getSessionInfo = getSessionInfo
+module.exports.hdrScreenshotBuffer = hdrScreenshotBuffer
+module.exports.hdrScreenshotAsync = hdrScreenshotAsync
+module.exports.captureWindow = captureWindow
+module.exports.compositeNotification = compositeNotification
 module.exports.log = log
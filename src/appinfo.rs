@@ -0,0 +1,399 @@
+use napi_derive::napi;
+use std::{collections::HashMap,fs::File,io::{self,BufReader,Read,Seek,SeekFrom},path::{Path,PathBuf}};
+use crate::{cache::FileCache,error::{self,ErrorCode},library,vdf::{self,BinaryReader,VdfValue}};
+
+const MAGIC_V27: u32 = 0x07564427;
+const MAGIC_V28: u32 = 0x07564428;
+const MAGIC_V29: u32 = 0x07564429;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct AppInfoEntry {
+    pub appid: u32,
+    pub name: String,
+    #[napi(js_name = "type")]
+    pub app_type: String,
+    /// Hash of `steam/games/<clienticon>.ico` in the Steam root
+    pub clienticon: String,
+    /// Parent app of a DLC, tool or demo
+    pub parent: Option<u32>,
+    pub last_updated: u32,
+    pub change_number: u32
+}
+
+// `appcache/appinfo.vdf`: a header followed by one binary KeyValues blob per app.
+// v29 moved every key into a string table at the end of the file
+pub struct AppInfoFile {
+    path: PathBuf,
+    version: u32,
+    strings: Option<Vec<String>>,
+    // Offset and size of every app's entry, so only the requested one is ever read
+    index: HashMap<u32,(u64,usize)>
+}
+
+fn read_u32(file: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8;4];
+    file.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_err(path: &Path,err: io::Error) -> error::Error {
+    match err.kind() {
+        io::ErrorKind::UnexpectedEof => error::err(ErrorCode::ParseFailed,format!("\"{}\" ends unexpectedly",path.display())),
+        _ => error::io_err(path,err)
+    }
+}
+
+impl AppInfoFile {
+    // One pass over the entry headers, skipping every KeyValues blob
+    pub fn open(path: &Path) -> error::Result<Self> {
+        let read = |err| read_err(path,err);
+        let file = File::open(path).map_err(|err| error::io_err(path,err))?;
+        let len = file.metadata().map_err(read)?.len();
+        let mut file = BufReader::new(file);
+
+        let magic = read_u32(&mut file).map_err(read)?;
+        let _universe = read_u32(&mut file).map_err(read)?;
+
+        let version = match magic {
+            MAGIC_V27 => 27,
+            MAGIC_V28 => 28,
+            MAGIC_V29 => 29,
+            other => return Err(error::err(ErrorCode::ParseFailed,format!("Unsupported appinfo.vdf magic 0x{:08x}",other)))
+        };
+
+        let (strings,entries_end) = match version {
+            29 => {
+                let mut offset = [0u8;8];
+                file.read_exact(&mut offset).map_err(read)?;
+                let offset = i64::from_le_bytes(offset);
+                let offset = u64::try_from(offset)
+                    .ok()
+                    .filter(|&offset| offset <= len)
+                    .ok_or_else(|| error::err(ErrorCode::ParseFailed,format!("String table offset {} is outside the file",offset)))?;
+
+                let entries_start = file.stream_position().map_err(read)?;
+                let mut table = Vec::new();
+
+                file.seek(SeekFrom::Start(offset))
+                    .and_then(|_| file.read_to_end(&mut table))
+                    .and_then(|_| file.seek(SeekFrom::Start(entries_start)))
+                    .map_err(read)?;
+
+                let mut reader = BinaryReader::new(&table);
+                let count = reader.read_u32()? as usize;
+                // Every string takes at least its terminator, so a corrupt count can't reserve more than the file holds
+                let mut strings = Vec::with_capacity(count.min(reader.remaining()));
+
+                for _ in 0..count {
+                    strings.push(reader.read_cstr()?);
+                }
+
+                (Some(strings),offset)
+            },
+            _ => (None,len)
+        };
+
+        let mut index = HashMap::new();
+
+        loop {
+            let appid = read_u32(&mut file).map_err(read)?;
+
+            if appid == 0 {
+                break
+            }
+
+            let size = read_u32(&mut file).map_err(read)?;
+            let offset = file.stream_position().map_err(read)?;
+
+            if offset + size as u64 > entries_end {
+                return Err(error::err(ErrorCode::ParseFailed,format!("Entry of {} runs past the end of its section",appid)))
+            }
+
+            index.insert(appid,(offset,size as usize));
+            file.seek_relative(size as i64).map_err(read)?;
+        }
+
+        Ok(AppInfoFile {
+            path: path.to_path_buf(),
+            version,
+            strings,
+            index
+        })
+    }
+
+    pub fn find(&self,appid: u32) -> error::Result<Option<AppInfoEntry>> {
+        Ok(self
+            .find_kv(appid)?
+            .map(|(kv,last_updated,change_number)| AppInfoEntry::from_kv(appid,&kv,last_updated,change_number))
+        )
+    }
+
+    // Reads and decodes the KeyValues blob of a single app.
+    // Returns the blob with the entry's last updated time and change number
+    pub fn find_kv(&self,appid: u32) -> error::Result<Option<(VdfValue,u32,u32)>> {
+        let Some(&(offset,size)) = self.index.get(&appid) else {
+            return Ok(None)
+        };
+
+        let mut data = vec![0u8;size];
+        let mut file = File::open(&self.path).map_err(|err| error::io_err(&self.path,err))?;
+
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut data))
+            .map_err(|err| read_err(&self.path,err))?;
+
+        let mut reader = BinaryReader::new(&data);
+        let _info_state = reader.read_u32()?;
+        let last_updated = reader.read_u32()?;
+        let _pics_token = reader.read_u64()?;
+        let _sha1 = reader.read_bytes(20)?;
+        let change_number = reader.read_u32()?;
+
+        if self.version >= 28 {
+            let _binary_sha1 = reader.read_bytes(20)?;
+        }
+
+        let kv = vdf::parse_binary(&mut reader,self.strings.as_deref())?;
+        Ok(Some((kv,last_updated,change_number)))
+    }
+}
+
+impl AppInfoEntry {
+    fn from_kv(appid: u32,kv: &VdfValue,last_updated: u32,change_number: u32) -> Self {
+        let common = kv.get_path("appinfo/common");
+        let get = |key: &str| common
+            .and_then(|common| common.get_str(key))
+            .unwrap_or_default()
+            .to_string();
+
+        AppInfoEntry {
+            appid,
+            name: get("name"),
+            app_type: get("type"),
+            clienticon: get("clienticon"),
+            parent: common
+                .and_then(|common| common.get_i64("parent"))
+                .map(|parent| parent as u32),
+            last_updated,
+            change_number
+        }
+    }
+}
+
+pub fn appinfo_path(steam_path: &Path) -> PathBuf {
+    steam_path
+        .join("appcache")
+        .join("appinfo.vdf")
+}
+
+// The index and every lookup are cached until appinfo.vdf changes, as it can be hundreds of MB and running-app detection polls it
+struct AppInfoCache {
+    file: AppInfoFile,
+    entries: HashMap<u32,Option<AppInfoEntry>>
+}
+
+static APPINFO_CACHE: FileCache<AppInfoCache> = FileCache::new();
+
+fn load_appinfo(path: &Path) -> error::Result<AppInfoCache> {
+    AppInfoFile::open(path).map(|file| AppInfoCache {
+        file,
+        entries: HashMap::new()
+    })
+}
+
+fn app_not_found(appid: u32) -> error::Error {
+    error::err(ErrorCode::AppNotFound,format!("{} not found in appinfo.vdf",appid))
+}
+
+pub fn app_info_entry(steam_path: &Path,appid: u32) -> error::Result<AppInfoEntry> {
+    let entry = APPINFO_CACHE.with(&appinfo_path(steam_path),load_appinfo,|cache| match cache.entries.get(&appid) {
+        Some(entry) => Ok(entry.clone()),
+        None => {
+            let entry = cache.file.find(appid)?;
+            cache.entries.insert(appid,entry.clone());
+            Ok(entry)
+        }
+    })?;
+
+    entry.ok_or_else(|| app_not_found(appid))
+}
+
+// The whole KeyValues blob of an app, with its last updated time and change number. Read and decoded on every call, through the cached index
+pub fn app_info_kv(steam_path: &Path,appid: u32) -> error::Result<(VdfValue,u32,u32)> {
+    APPINFO_CACHE
+        .with(&appinfo_path(steam_path),load_appinfo,|cache| cache.file.find_kv(appid))?
+        .ok_or_else(|| app_not_found(appid))
+}
+
+// Steam ships these as apps, and they carry an AppId while running alongside (or instead of) the game
+pub const NON_GAME_APPIDS: [u32;22] = [
+    // Steamworks Common Redistributables
+    228980,
+    // SteamVR
+    250820,
+    // Steam Linux Runtime 1.0 (scout), 2.0 (soldier) and 3.0 (sniper)
+    1070560,
+    1391110,
+    1628350,
+    // Proton releases
+    858280,
+    930400,
+    961940,
+    996510,
+    1054830,
+    1113280,
+    1245040,
+    1420170,
+    1580130,
+    1887720,
+    2348590,
+    2805730,
+    3658110,
+    // Proton Experimental, Proton Hotfix, and the BattlEye/EasyAntiCheat runtimes
+    1493710,
+    2180100,
+    1161040,
+    1826330
+];
+
+// appinfo `common/type` values that are never the game being played
+const NON_GAME_TYPES: [&str;2] = ["tool","config"];
+
+pub fn is_non_game(steam_path: Option<&Path>,appid: u32) -> bool {
+    if NON_GAME_APPIDS.contains(&appid) {
+        return true
+    }
+
+    if crate::shortcuts::is_shortcut_appid(appid) {
+        return false
+    }
+
+    steam_path
+        .and_then(|steam_path| app_info_entry(steam_path,appid).ok())
+        .is_some_and(|entry| NON_GAME_TYPES
+            .iter()
+            .any(|app_type| entry.app_type.eq_ignore_ascii_case(app_type))
+        )
+}
+
+#[napi]
+pub fn get_app_info_entry(appid: u32) -> error::Result<AppInfoEntry> {
+    app_info_entry(&library::steam_root()?,appid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use crate::fixture;
+
+    #[test]
+    fn parses_every_layout() {
+        for version in [27,28,29] {
+            let file = AppInfoFile::open(&fixture(&format!("appinfo_v{}.vdf",version))).unwrap();
+            assert_eq!(file.version,version);
+            assert_eq!(file.strings.is_some(),version == 29);
+
+            let game = file.find(440).unwrap().unwrap();
+            assert_eq!(game.name,"Team Fortress 2");
+            assert_eq!(game.app_type,"Game");
+            assert_eq!(game.clienticon,"e4ad9cf1b7dc8475c1118625daf9abd4bdcbcad0");
+            assert_eq!(game.parent,None);
+            assert_eq!((game.last_updated,game.change_number),(1700000000,9000));
+
+            let dlc = file.find(1000).unwrap().unwrap();
+            assert_eq!(dlc.app_type,"DLC");
+            assert_eq!(dlc.parent,Some(440));
+
+            assert!(file.find(730).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn cached_lookups() {
+        let steam = tempfile::tempdir().unwrap();
+        fs::create_dir_all(steam.path().join("appcache")).unwrap();
+        fs::copy(fixture("appinfo_v29.vdf"),appinfo_path(steam.path())).unwrap();
+
+        assert_eq!(app_info_entry(steam.path(),440).unwrap().name,"Team Fortress 2");
+        assert_eq!(app_info_entry(steam.path(),730).err().unwrap().status,ErrorCode::AppNotFound);
+
+        let (kv,last_updated,_) = app_info_kv(steam.path(),1000).unwrap();
+        assert_eq!(kv.get_path("appinfo/common/name").and_then(VdfValue::as_str),Some("Soundtrack"));
+        assert_eq!(last_updated,1700000100);
+        assert_eq!(app_info_kv(steam.path(),730).err().unwrap().status,ErrorCode::AppNotFound);
+    }
+
+    fn open_modified(name: &str,modify: impl FnOnce(&mut Vec<u8>)) -> error::Result<AppInfoFile> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("appinfo.vdf");
+        let mut data = fs::read(fixture(name)).unwrap();
+
+        modify(&mut data);
+        fs::write(&path,data).unwrap();
+        AppInfoFile::open(&path)
+    }
+
+    #[test]
+    fn rejects_corrupt_string_table_offset() {
+        let len = fs::metadata(fixture("appinfo_v29.vdf")).unwrap().len() as i64;
+
+        for offset in [-1i64,i64::MAX,len + 1] {
+            let err = open_modified("appinfo_v29.vdf",|data| data[8..16].copy_from_slice(&offset.to_le_bytes())).err().unwrap();
+            assert_eq!(err.status,ErrorCode::ParseFailed);
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_entries() {
+        for version in [27,28,29] {
+            let name = format!("appinfo_v{}.vdf",version);
+            let err = open_modified(&name,|data| match version {
+                // Cut into the last entry, moving the string table offset along with it
+                29 => {
+                    let offset = i64::from_le_bytes(data[8..16].try_into().unwrap());
+                    data.drain(offset as usize - 24..offset as usize);
+                    data[8..16].copy_from_slice(&(offset - 24).to_le_bytes());
+                },
+                _ => data.truncate(data.len() - 20)
+            }).err().unwrap();
+
+            assert_eq!(err.status,ErrorCode::ParseFailed,"v{}",version);
+        }
+    }
+
+    #[test]
+    fn reads_entries_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("appinfo.vdf");
+        fs::copy(fixture("appinfo_v28.vdf"),&path).unwrap();
+
+        let file = AppInfoFile::open(&path).unwrap();
+        assert_eq!(file.index.len(),2);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(file.find(440).err().unwrap().status,ErrorCode::FileNotFound);
+        assert!(file.find(730).unwrap().is_none());
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let mut data = Vec::new();
+
+        for _ in 0..10_000 {
+            data.extend_from_slice(b"\x00a\x00");
+        }
+
+        let err = vdf::parse_binary(&mut BinaryReader::new(&data),None).err().unwrap();
+        assert_eq!(err.status,ErrorCode::ParseFailed);
+    }
+
+    #[test]
+    fn read_bytes_does_not_overflow() {
+        let mut reader = BinaryReader::new(b"abcd");
+        reader.seek(2);
+
+        assert!(reader.read_bytes(usize::MAX).is_err());
+    }
+}
@@ -0,0 +1,213 @@
+use napi_derive::napi;
+use std::path::Path;
+use extern_log::error;
+use crate::{error::{self,ErrorCode},library::{self,LibraryFolder},vdf};
+
+// `EAppState` bits stored in an appmanifest's `StateFlags`
+const APP_STATES: [(u32,&str);21] = [
+    (1,"Uninstalled"),
+    (2,"UpdateRequired"),
+    (4,"FullyInstalled"),
+    (8,"Encrypted"),
+    (16,"Locked"),
+    (32,"FilesMissing"),
+    (64,"AppRunning"),
+    (128,"FilesCorrupt"),
+    (256,"UpdateRunning"),
+    (512,"UpdatePaused"),
+    (1024,"UpdateStarted"),
+    (2048,"Uninstalling"),
+    (4096,"BackupRunning"),
+    (65536,"Reconfiguring"),
+    (131072,"Validating"),
+    (262144,"AddingFiles"),
+    (524288,"Preallocating"),
+    (1048576,"Downloading"),
+    (2097152,"Staging"),
+    (4194304,"Committing"),
+    (8388608,"UpdateStopping")
+];
+
+const FULLY_INSTALLED: u32 = 4;
+const UPDATE_REQUIRED: u32 = 2;
+// UpdateRunning | UpdateStarted | Validating | AddingFiles | Preallocating | Downloading | Staging | Committing
+const UPDATING: u32 = 256 | 1024 | 131072 | 262144 | 524288 | 1048576 | 2097152 | 4194304;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct AppManifest {
+    pub appid: u32,
+    pub name: String,
+    pub installdir: String,
+    /// Absolute path of `steamapps/common/<installdir>` in the owning library
+    pub install_path: String,
+    pub state_flags: u32,
+    /// Names of every `EAppState` bit set in `state_flags`
+    pub states: Vec<String>,
+    pub installed: bool,
+    pub update_required: bool,
+    pub updating: bool,
+    pub buildid: u32,
+    /// Unix timestamp
+    pub last_updated: i64,
+    pub size_on_disk: i64,
+    /// SteamID64
+    pub last_owner: String,
+    pub library_index: u32,
+    pub library_path: String
+}
+
+pub fn decode_state_flags(flags: u32) -> Vec<String> {
+    APP_STATES
+        .iter()
+        .filter(|(bit,_)| flags & bit != 0)
+        .map(|(_,name)| name.to_string())
+        .collect()
+}
+
+pub fn parse_app_manifest(acf: &Path,folder: &LibraryFolder) -> error::Result<AppManifest> {
+    let parsed = vdf::load(acf)?;
+    let state = parsed
+        .get("AppState")
+        .ok_or_else(|| error::err(ErrorCode::KeyNotFound,format!("\"AppState\" not found in \"{}\"",acf.display())))?;
+
+    let get = |key: &str| state.get_str(key).unwrap_or_default().to_string();
+    let get_num = |key: &str| state.get_i64(key).unwrap_or(0);
+
+    let installdir = get("installdir");
+    let state_flags = get_num("StateFlags") as u32;
+
+    Ok(AppManifest {
+        appid: get_num("appid") as u32,
+        name: get("name"),
+        install_path: folder
+            .steamapps()
+            .join("common")
+            .join(&installdir)
+            .to_string_lossy()
+            .into_owned(),
+        installdir,
+        state_flags,
+        states: decode_state_flags(state_flags),
+        installed: state_flags & FULLY_INSTALLED != 0,
+        update_required: state_flags & UPDATE_REQUIRED != 0,
+        updating: state_flags & UPDATING != 0,
+        buildid: get_num("buildid") as u32,
+        last_updated: get_num("LastUpdated"),
+        size_on_disk: get_num("SizeOnDisk"),
+        last_owner: get("LastOwner"),
+        library_index: folder.index,
+        library_path: folder.path.clone()
+    })
+}
+
+pub fn app_manifest(folders: &[LibraryFolder],appid: u32) -> error::Result<AppManifest> {
+    let folder = library::find_app_library(folders,appid)
+        .ok_or_else(|| error::err(ErrorCode::AppNotFound,format!("Failed to locate \"appmanifest_{}.acf\" in any library folder",appid)))?;
+
+    parse_app_manifest(&folder.steamapps().join(format!("appmanifest_{}.acf",appid)),folder)
+}
+
+#[napi]
+pub fn get_app_manifest(appid: u32) -> error::Result<AppManifest> {
+    let folders = library::library_folders(&library::steam_root()?)?;
+    app_manifest(&folders,appid)
+}
+
+pub fn installed_apps(folders: &[LibraryFolder]) -> Vec<AppManifest> {
+    let mut apps: Vec<AppManifest> = Vec::new();
+
+    for folder in folders {
+        let entries = match std::fs::read_dir(folder.steamapps()) {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!("Failed to read \"{}\": {}",folder.steamapps().display(),err);
+                continue
+            }
+        };
+
+        for entry in entries.flatten() {
+            let filename = entry.file_name().to_string_lossy().into_owned();
+
+            if !filename.starts_with("appmanifest_") || !filename.ends_with(".acf") {
+                continue
+            }
+
+            match parse_app_manifest(&entry.path(),folder) {
+                Ok(manifest) if apps.iter().any(|app| app.appid == manifest.appid) => {},
+                Ok(manifest) => apps.push(manifest),
+                Err(err) => error!("Failed to parse \"{}\": {}",entry.path().display(),err)
+            }
+        }
+    }
+
+    apps.sort_by_key(|app| app.appid);
+    apps
+}
+
+#[napi]
+pub fn get_installed_apps() -> error::Result<Vec<AppManifest>> {
+    let folders = library::library_folders(&library::steam_root()?)?;
+    Ok(installed_apps(&folders))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::fixture;
+
+    fn steam_library() -> LibraryFolder {
+        LibraryFolder {
+            index: 0,
+            path: fixture("steam").to_string_lossy().into_owned(),
+            label: "".to_string(),
+            contentid: "".to_string(),
+            totalsize: 0,
+            apps: HashMap::new()
+        }
+    }
+
+    #[test]
+    fn decodes_state_flags() {
+        assert_eq!(decode_state_flags(1030),["UpdateRequired","FullyInstalled","UpdateStarted"]);
+        assert_eq!(decode_state_flags(4 | 8388608),["FullyInstalled","UpdateStopping"]);
+        assert!(decode_state_flags(0).is_empty());
+        assert!(decode_state_flags(8192 | 16384 | 32768).is_empty());
+    }
+
+    #[test]
+    fn parses_installed_manifest() {
+        let folder = steam_library();
+        let manifest = parse_app_manifest(&folder.steamapps().join("appmanifest_440.acf"),&folder).unwrap();
+
+        assert_eq!(manifest.appid,440);
+        assert_eq!(manifest.name,"Team Fortress 2");
+        assert_eq!(manifest.install_path,folder.steamapps().join("common").join("Team Fortress 2").to_string_lossy());
+        assert_eq!(manifest.states,["FullyInstalled"]);
+        assert!(manifest.installed && !manifest.update_required && !manifest.updating);
+        assert_eq!(manifest.buildid,14207891);
+        assert_eq!(manifest.last_updated,1712345678);
+        assert_eq!(manifest.size_on_disk,28316485421);
+        assert_eq!(manifest.last_owner,"76561197960287930");
+        assert_eq!((manifest.library_index,manifest.library_path),(0,folder.path));
+    }
+
+    #[test]
+    fn derives_update_state() {
+        let folder = steam_library();
+        let manifest = parse_app_manifest(&folder.steamapps().join("appmanifest_228980.acf"),&folder).unwrap();
+
+        assert_eq!(manifest.state_flags,1030);
+        assert!(manifest.installed && manifest.update_required && manifest.updating);
+    }
+
+    #[test]
+    fn finds_manifests_across_libraries() {
+        let folders = [steam_library()];
+
+        assert_eq!(app_manifest(&folders,228980).unwrap().name,"Steamworks Common Redistributables");
+        assert_eq!(app_manifest(&folders,12).unwrap_err().status,ErrorCode::AppNotFound);
+        assert_eq!(installed_apps(&folders).iter().map(|app| app.appid).collect::<Vec<_>>(),[440,228980]);
+    }
+}
@@ -0,0 +1,308 @@
+use napi_derive::napi;
+use std::{fs,path::{Path,PathBuf}};
+use crate::{appinfo,error,library};
+
+#[napi(object)]
+#[derive(Clone,Debug,PartialEq)]
+pub struct Artwork {
+    /// `icon`, `header`, `library_600x900`, `library_600x900_2x`, `library_hero`, `library_hero_blur`, `library_header` or `logo`
+    pub kind: String,
+    /// `librarycache`, `hicolor` or `clienticon`
+    pub source: String,
+    pub path: String,
+    pub width: u32,
+    pub height: u32
+}
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct AppArtwork {
+    pub appid: u32,
+    /// Path of the largest icon found, or an empty string
+    pub icon: String,
+    pub images: Vec<Artwork>
+}
+
+// Steam's librarycache names, with the `<appid>_` prefix of the flat layout removed
+const KINDS: [&str;8] = [
+    "icon",
+    "header",
+    "library_600x900",
+    "library_600x900_2x",
+    "library_hero",
+    "library_hero_blur",
+    "library_header",
+    "logo"
+];
+
+const HICOLOR_SIZES: [&str;6] = [
+    "256x256",
+    "128x128",
+    "64x64",
+    "32x32",
+    "24x24",
+    "16x16"
+];
+
+fn artwork(kind: &str,source: &str,path: &Path) -> Option<Artwork> {
+    // Only the header is read. ICO files report their largest frame
+    let (width,height) = image::io::Reader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+
+    Some(Artwork {
+        kind: kind.to_string(),
+        source: source.to_string(),
+        path: path.to_string_lossy().to_string(),
+        width,
+        height
+    })
+}
+
+fn is_hash(stem: &str) -> bool {
+    stem.len() == 40 && stem.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Newer clients keep art in `librarycache/<appid>/`, with localised variants in hashed subfolders
+// and the icon saved as `<clienticon hash>.jpg`
+fn scan_app_dir(dir: &Path,images: &mut Vec<Artwork>,depth: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return
+    };
+
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            if depth == 0 {
+                scan_app_dir(&path,images,depth + 1);
+            }
+
+            continue
+        }
+
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue
+        };
+
+        let kind = KINDS
+            .iter()
+            .find(|kind| kind.eq_ignore_ascii_case(stem))
+            .copied()
+            .or(if is_hash(stem) { Some("icon") } else { None });
+
+        if let Some(image) = kind.and_then(|kind| artwork(kind,"librarycache",&path)) {
+            images.push(image);
+        }
+    }
+}
+
+pub fn librarycache_artwork(steam_path: &Path,appid: u32) -> Vec<Artwork> {
+    let cache = steam_path.join("appcache").join("librarycache");
+    let mut images = Vec::new();
+
+    // Older clients keep every file flat, as `<appid>_<kind>.<ext>`
+    if let Ok(entries) = fs::read_dir(&cache) {
+        let prefix = format!("{}_",appid);
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+
+        paths.sort();
+
+        for path in paths {
+            let kind = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.strip_prefix(&prefix))
+                .and_then(|stem| KINDS.iter().find(|kind| kind.eq_ignore_ascii_case(stem)));
+
+            if let Some(image) = kind.and_then(|kind| artwork(kind,"librarycache",&path)) {
+                images.push(image);
+            }
+        }
+    }
+
+    scan_app_dir(&cache.join(appid.to_string()),&mut images,0);
+    images
+}
+
+// Desktop icons installed by Steam's "Create Desktop Shortcut"
+pub fn hicolor_icons(hicolor: &Path,appid: u32) -> Vec<Artwork> {
+    HICOLOR_SIZES
+        .iter()
+        .filter_map(|size| artwork("icon","hicolor",&hicolor
+            .join(size)
+            .join("apps")
+            .join(format!("steam_icon_{}.png",appid))
+        ))
+        .collect()
+}
+
+// `steam/games/<clienticon>.ico`, usually the only icon above 32px for games without a desktop shortcut
+pub fn clienticon(steam_path: &Path,appid: u32) -> Option<Artwork> {
+    let entry = appinfo::app_info_entry(steam_path,appid).ok()?;
+
+    if entry.clienticon.is_empty() {
+        return None
+    }
+
+    artwork("icon","clienticon",&steam_path
+        .join("steam")
+        .join("games")
+        .join(format!("{}.ico",entry.clienticon))
+    )
+}
+
+// Without a Steam root, only hicolor icons are looked up
+pub fn app_artwork(steam_path: Option<&Path>,hicolor: Option<&Path>,appid: u32) -> AppArtwork {
+    let mut images = hicolor
+        .map(|hicolor| hicolor_icons(hicolor,appid))
+        .unwrap_or_default();
+
+    if let Some(steam_path) = steam_path {
+        images.extend(clienticon(steam_path,appid));
+        images.extend(librarycache_artwork(steam_path,appid));
+    }
+
+    // Ties go to the earlier source, as hicolor PNGs keep their transparency
+    let icon = images
+        .iter()
+        .filter(|image| image.kind == "icon")
+        .fold(None,|best: Option<&Artwork>,image| match best {
+            Some(best) if best.width.min(best.height) >= image.width.min(image.height) => Some(best),
+            _ => Some(image)
+        })
+        .map(|image| image.path.clone())
+        .unwrap_or_default();
+
+    AppArtwork {
+        appid,
+        icon,
+        images
+    }
+}
+
+pub fn hicolor_dir() -> Option<PathBuf> {
+    #[cfg(target_os="linux")] {
+        crate::linux::data_local_dir().map(|share| share.join("icons").join("hicolor"))
+    }
+
+    #[cfg(not(target_os="linux"))] {
+        None
+    }
+}
+
+#[napi]
+pub fn get_app_artwork(appid: u32) -> error::Result<AppArtwork> {
+    Ok(app_artwork(Some(&library::steam_root()?),hicolor_dir().as_deref(),appid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{RgbImage,RgbaImage};
+    use crate::fixture;
+
+    const ICON_HASH: &str = "e4ad9cf1b7dc8475c1118625daf9abd4bdcbcad0";
+
+    fn jpeg(path: &Path,width: u32,height: u32) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        RgbImage::new(width,height).save(path).unwrap();
+    }
+
+    fn found(images: &[Artwork]) -> Vec<(&str,u32,u32)> {
+        images.iter().map(|image| (image.kind.as_str(),image.width,image.height)).collect()
+    }
+
+    #[test]
+    fn hicolor_without_steam_root() {
+        let hicolor = tempfile::tempdir().unwrap();
+        let dir = hicolor.path().join("256x256").join("apps");
+        fs::create_dir_all(&dir).unwrap();
+        RgbaImage::new(256,256).save(dir.join("steam_icon_440.png")).unwrap();
+
+        let art = app_artwork(None,Some(hicolor.path()),440);
+        assert_eq!(art.icon,dir.join("steam_icon_440.png").to_string_lossy());
+        assert_eq!(art.images.len(),1);
+        assert_eq!((art.images[0].width,art.images[0].height),(256,256));
+
+        assert!(app_artwork(None,Some(hicolor.path()),730).icon.is_empty());
+    }
+
+    #[test]
+    fn flat_librarycache_layout() {
+        let steam = tempfile::tempdir().unwrap();
+        let cache = steam.path().join("appcache").join("librarycache");
+        jpeg(&cache.join("440_header.jpg"),460,215);
+        jpeg(&cache.join("440_library_600x900.jpg"),600,900);
+        jpeg(&cache.join("440_Icon.jpg"),32,32);
+        jpeg(&cache.join("440_unknown.jpg"),8,8);
+        jpeg(&cache.join("4400_header.jpg"),460,215);
+
+        let images = librarycache_artwork(steam.path(),440);
+
+        assert_eq!(found(&images),[("icon",32,32),("header",460,215),("library_600x900",600,900)]);
+        assert!(images.iter().all(|image| image.source == "librarycache"));
+        assert_eq!(images[0].path,cache.join("440_Icon.jpg").to_string_lossy());
+    }
+
+    #[test]
+    fn per_app_librarycache_layout() {
+        let steam = tempfile::tempdir().unwrap();
+        let dir = steam.path().join("appcache").join("librarycache").join("1091500");
+        // A localised variant, then a folder nested too deep to be scanned
+        jpeg(&dir.join("1".repeat(40)).join("library_600x900.jpg"),600,900);
+        jpeg(&dir.join("deeper").join("nested").join("logo.jpg"),640,360);
+        jpeg(&dir.join(format!("{}.jpg",ICON_HASH)),64,64);
+        jpeg(&dir.join("header.jpg"),460,215);
+        jpeg(&dir.join("notes.jpg"),8,8);
+        jpeg(&dir.join("abc.jpg"),8,8);
+
+        let images = librarycache_artwork(steam.path(),1091500);
+
+        assert_eq!(found(&images),[("library_600x900",600,900),("icon",64,64),("header",460,215)]);
+        assert!(librarycache_artwork(steam.path(),440).is_empty());
+    }
+
+    #[test]
+    fn picks_the_largest_icon_across_sources() {
+        let steam = tempfile::tempdir().unwrap();
+        let hicolor = tempfile::tempdir().unwrap();
+        let cache = steam.path().join("appcache").join("librarycache");
+        let games = steam.path().join("steam").join("games");
+
+        fs::create_dir_all(&games).unwrap();
+        fs::create_dir_all(&cache).unwrap();
+        fs::copy(fixture("appinfo_v29.vdf"),steam.path().join("appcache").join("appinfo.vdf")).unwrap();
+        fs::copy(fixture("icons/multi.ico"),games.join(format!("{}.ico",ICON_HASH))).unwrap();
+
+        for appid in [440,1091500] {
+            let dir = hicolor.path().join("32x32").join("apps");
+            fs::create_dir_all(&dir).unwrap();
+            RgbaImage::new(32,32).save(dir.join(format!("steam_icon_{}.png",appid))).unwrap();
+        }
+
+        jpeg(&cache.join("440_icon.jpg"),64,64);
+
+        let art = app_artwork(Some(steam.path()),Some(hicolor.path()),440);
+        let sources: Vec<&str> = art.images.iter().map(|image| image.source.as_str()).collect();
+
+        assert_eq!(sources,["hicolor","clienticon","librarycache"]);
+        assert_eq!(art.icon,games.join(format!("{}.ico",ICON_HASH)).to_string_lossy());
+        assert_eq!((art.images[1].width,art.images[1].height),(256,256));
+
+        // Equal sizes keep the hicolor icon
+        jpeg(&cache.join("1091500").join(format!("{}.jpg",ICON_HASH)),32,32);
+        let art = app_artwork(Some(steam.path()),Some(hicolor.path()),1091500);
+
+        assert_eq!(art.images.len(),2);
+        assert!(art.icon.starts_with(&*hicolor.path().to_string_lossy()));
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fs,path::{Path,PathBuf},sync::Mutex,time::SystemTime};
+use crate::error;
+
+struct Cached<T> {
+    path: PathBuf,
+    modified: SystemTime,
+    value: T
+}
+
+// A value loaded from a file, kept until the file (or the requested path) changes
+pub struct FileCache<T> {
+    cached: Mutex<Option<Cached<T>>>
+}
+
+impl<T> Default for FileCache<T> {
+    fn default() -> Self {
+        FileCache::new()
+    }
+}
+
+impl<T> FileCache<T> {
+    pub const fn new() -> Self {
+        FileCache {
+            cached: Mutex::new(None)
+        }
+    }
+
+    // Calls `with` on the value for `path`, running `load` first if nothing is cached or the file was modified since
+    pub fn with<R,L,F>(&self,path: &Path,load: L,with: F) -> error::Result<R>
+    where
+        L: FnOnce(&Path) -> error::Result<T>,
+        F: FnOnce(&mut T) -> error::Result<R>
+    {
+        let modified = fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .map_err(|err| error::io_err(path,err))?;
+
+        let mut cached = self.cached.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let value = match cached.as_mut() {
+            Some(cached) if cached.path == path && cached.modified == modified => &mut cached.value,
+            _ => &mut cached
+                .insert(Cached {
+                    path: path.to_path_buf(),
+                    modified,
+                    value: load(path)?
+                })
+                .value
+        };
+
+        with(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell,fs::File,time::Duration};
+
+    #[test]
+    fn reloads_when_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.txt");
+        fs::write(&path,"one").unwrap();
+
+        let cache: FileCache<String> = FileCache::new();
+        let loads = Cell::new(0);
+        let load = |path: &Path| {
+            loads.set(loads.get() + 1);
+            Ok(fs::read_to_string(path).unwrap())
+        };
+
+        assert_eq!(cache.with(&path,load,|val| Ok(val.clone())).unwrap(),"one");
+        assert_eq!(cache.with(&path,load,|val| Ok(val.clone())).unwrap(),"one");
+        assert_eq!(loads.get(),1);
+
+        fs::write(&path,"two").unwrap();
+        File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now() + Duration::from_secs(60)))
+            .unwrap();
+
+        assert_eq!(cache.with(&path,load,|val| Ok(val.clone())).unwrap(),"two");
+        assert_eq!(loads.get(),2);
+    }
+
+    #[test]
+    fn missing_file() {
+        let cache: FileCache<()> = FileCache::new();
+        let err = cache.with(Path::new("/nonexistent/data.txt"),|_| Ok(()),|_| Ok(())).err().unwrap();
+
+        assert_eq!(err.status,error::ErrorCode::FileNotFound);
+    }
+}
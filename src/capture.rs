@@ -0,0 +1,181 @@
+use std::sync::{Arc,atomic::{AtomicBool,Ordering}};
+use napi::{CallContext,Env,JsBoolean,JsFunction,JsObject,JsUndefined,Task,bindgen_prelude::{AsyncTask,Buffer}};
+use napi_derive::{js_function,napi};
+use extern_log::{info,error};
+use image::RgbaImage;
+use screenshots::Screen;
+use crate::{encode::{self,EncodeOptions,ImageFormat},error::{self,ErrorCode},get_monitors};
+
+#[napi(object)]
+pub struct ScreenshotBuffer {
+    pub data: Buffer,
+    /// `png`, `jpeg` or `webp`
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// Electron display id of the captured monitor, which differs from the requested one after a fallback to the primary monitor
+    pub monitor_id: u32
+}
+
+fn electron_display_id(screen: &Screen) -> Option<u32> {
+    get_monitors()
+        .ok()?
+        .into_iter()
+        .find(|monitor| monitor.screenshots_displayinfo_id == screen.display_info.id)
+        .map(|monitor| monitor.electron_display_id)
+}
+
+// The screen matching an Electron display id, falling back to the primary monitor
+pub fn find_screen(monitor_id: u32) -> error::Result<Screen> {
+    let screenshots_displayinfo_id = get_monitors()
+        .ok()
+        .and_then(|monitors| monitors
+            .into_iter()
+            .find(|monitor| monitor.electron_display_id == monitor_id)
+            .map(|monitor| monitor.screenshots_displayinfo_id)
+        );
+
+    let screens = Screen::all()
+        .map_err(|err| error::err(ErrorCode::MonitorNotFound,format!("Failed to parse monitor list: {}",err)))?;
+
+    let mut primary = None;
+
+    for screen in screens {
+        if screenshots_displayinfo_id.is_some_and(|id| screen.display_info.id == id) {
+            info!("\"screen.display_info.id\" ({}) matched to \"monitor_id\" (electron_display_id: {} | screenshots_displayinfo_id: {}) successfully",screen.display_info.id,monitor_id,screenshots_displayinfo_id.unwrap_or(0));
+            return Ok(screen)
+        }
+
+        if screen.display_info.is_primary {
+            primary = Some(screen);
+        }
+    }
+
+    match primary {
+        Some(primary) => {
+            error!("No match found for \"monitor_id\" ({}) - fallback to primary monitor",monitor_id);
+            Ok(primary)
+        },
+        None => Err(error::err(ErrorCode::MonitorNotFound,format!("Failed to locate screen matching \"monitor_id\" ({}), and no primary monitor located",monitor_id)))
+    }
+}
+
+// Order of elements in `area` is y/x/w/h, matching `screen.capture_area()`
+pub fn capture(screen: &Screen,area: Option<(u32,u32,u32,u32)>) -> error::Result<RgbaImage> {
+    let (mode,capture) = match area {
+        Some((y,x,w,h)) => ("window",screen.capture_area(y as i32,x as i32,w,h)),
+        None => ("screen",screen.capture())
+    };
+
+    let img = capture.map_err(|err| error::err(ErrorCode::CaptureFailed,format!("Failed to capture {}: {}",mode,err)))?;
+    let (width,height) = img.dimensions();
+
+    // `screenshots` may pin a different `image` release, so the pixels are handed over raw
+    RgbaImage::from_raw(width,height,img.into_raw())
+        .ok_or_else(|| error::err(ErrorCode::CaptureFailed,format!("Captured {} has an invalid buffer size",mode)))
+}
+
+pub fn screenshot(monitor_id: u32,area: Option<(u32,u32,u32,u32)>,format: ImageFormat) -> error::Result<ScreenshotBuffer> {
+    let screen = find_screen(monitor_id)?;
+    let img = capture(&screen,area)?;
+    let (width,height) = img.dimensions();
+
+    Ok(ScreenshotBuffer {
+        data: encode::encode(img,format)?.into(),
+        format: format.name().to_string(),
+        width,
+        height,
+        monitor_id: electron_display_id(&screen).unwrap_or(monitor_id)
+    })
+}
+
+/// Captures to memory, avoiding the disk round-trip of `hdrScreenshot`
+#[napi]
+pub fn hdr_screenshot_buffer(monitor_id: u32,area: Option<(u32,u32,u32,u32)>,options: Option<EncodeOptions>) -> error::Result<ScreenshotBuffer> {
+    screenshot(monitor_id,area,ImageFormat::from_options(&options.unwrap_or_default())?)
+}
+
+// Wrapped onto an `AbortSignal`, so every task started with the same signal shares one flag
+struct AbortFlag(Arc<AtomicBool>);
+
+#[js_function(1)]
+fn on_abort(ctx: CallContext) -> napi::Result<JsUndefined> {
+    let signal = ctx.this_unchecked::<JsObject>();
+    ctx.env.unwrap::<AbortFlag>(&signal)?.0.store(true,Ordering::SeqCst);
+    ctx.env.get_undefined()
+}
+
+// napi's own `AbortSignal` rejects with its `Cancelled` status before the task can map it, so the signal is watched here instead
+fn abort_flag(env: Env,signal: Option<JsObject>) -> napi::Result<Arc<AtomicBool>> {
+    let Some(mut signal) = signal else {
+        return Ok(Arc::new(AtomicBool::new(false)))
+    };
+
+    if let Ok(flag) = env.unwrap::<AbortFlag>(&signal) {
+        return Ok(flag.0.clone())
+    }
+
+    let flag = Arc::new(AtomicBool::new(signal.get_named_property::<JsBoolean>("aborted")?.get_value()?));
+    env.wrap(&mut signal,AbortFlag(flag.clone()))?;
+
+    let add_event_listener = signal.get_named_property::<JsFunction>("addEventListener")?;
+    let listener = env.create_function("onabort",on_abort)?;
+    add_event_listener.call(Some(&signal),&[env.create_string("abort")?.into_unknown(),listener.into_unknown()])?;
+
+    Ok(flag)
+}
+
+fn cancelled() -> error::Error {
+    error::err(ErrorCode::Cancelled,"Screenshot was aborted")
+}
+
+pub struct ScreenshotTask {
+    monitor_id: u32,
+    area: Option<(u32,u32,u32,u32)>,
+    format: ImageFormat,
+    aborted: Arc<AtomicBool>
+}
+
+impl Task for ScreenshotTask {
+    // Failures are passed on to `resolve`, as `compute` can only reject with a napi `Status`
+    type Output = error::Result<ScreenshotBuffer>;
+    type JsValue = ScreenshotBuffer;
+
+    fn compute(&mut self) -> napi::Result<Self::Output> {
+        if self.aborted.load(Ordering::SeqCst) {
+            return Ok(Err(cancelled()))
+        }
+
+        Ok(screenshot(self.monitor_id,self.area,self.format))
+    }
+
+    fn resolve(&mut self,env: Env,output: Self::Output) -> napi::Result<Self::JsValue> {
+        if self.aborted.load(Ordering::SeqCst) {
+            return Err(error::to_js(env,cancelled()))
+        }
+
+        output.map_err(|err| error::to_js(env,err))
+    }
+
+    fn reject(&mut self,env: Env,err: napi::Error) -> napi::Result<Self::JsValue> {
+        match self.aborted.load(Ordering::SeqCst) {
+            true => Err(error::to_js(env,cancelled())),
+            false => Err(err)
+        }
+    }
+}
+
+/// Captures and encodes on a libuv worker thread. Aborting `signal` rejects with code `CANCELLED` once the worker returns,
+/// and a capture already in progress is discarded
+#[napi(ts_args_type = "monitorId: number, area?: [number, number, number, number] | undefined | null, options?: EncodeOptions | undefined | null, signal?: AbortSignal | undefined | null",ts_return_type = "Promise<ScreenshotBuffer>")]
+pub fn hdr_screenshot_async(env: Env,monitor_id: u32,area: Option<(u32,u32,u32,u32)>,options: Option<EncodeOptions>,signal: Option<JsObject>) -> error::Result<AsyncTask<ScreenshotTask>> {
+    let aborted = abort_flag(env,signal)
+        .map_err(|err| error::err(ErrorCode::InvalidArgument,format!("Invalid \"signal\": {}",err.reason)))?;
+
+    Ok(AsyncTask::new(ScreenshotTask {
+        monitor_id,
+        area,
+        format: ImageFormat::from_options(&options.unwrap_or_default())?,
+        aborted
+    }))
+}
@@ -0,0 +1,234 @@
+use napi_derive::napi;
+use std::{collections::HashMap,env,fs,path::{Path,PathBuf}};
+use crate::{appinfo,appmanifest,error::{self,ErrorCode},library::{self,LibraryFolder},vdf};
+
+// "SteamPlay 2.0 Manifests", whose appinfo maps each official tool name (e.g. `proton_experimental`) to the app it installs as
+const STEAMPLAY_MANIFESTS_APPID: u32 = 891390;
+
+// Steam's global default tool is stored against appid 0
+const DEFAULT_MAPPING: u32 = 0;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct CompatInfo {
+    pub appid: u32,
+    /// Tool name from `CompatToolMapping`, e.g. `proton_experimental` or `GE-Proton9-7`. Empty for native games
+    pub tool_name: String,
+    pub tool_display_name: String,
+    /// Empty when the tool is not installed
+    pub tool_path: String,
+    /// No tool is set for the game itself, so Steam's global default applies
+    pub is_default_tool: bool,
+    /// Wine prefix, `steamapps/compatdata/<appid>/pfx` in the game's library folder
+    pub prefix_path: String,
+    pub prefix_exists: bool
+}
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct CompatTool {
+    pub name: String,
+    pub display_name: String,
+    pub path: PathBuf
+}
+
+// `CompatToolMapping` in `config/config.vdf`, as appid => tool name. A fresh install has no config.vdf, and so no mapping
+pub fn compat_tool_mapping(steam_path: &Path) -> error::Result<HashMap<u32,String>> {
+    let parsed = match vdf::load(&steam_path.join("config").join("config.vdf")) {
+        Ok(parsed) => parsed,
+        Err(err) if err.status == ErrorCode::FileNotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err)
+    };
+
+    Ok(parsed
+        .get_path("InstallConfigStore/Software/Valve/Steam/CompatToolMapping")
+        .map(|mapping| mapping
+            .entries()
+            .iter()
+            .filter_map(|(appid,tool)| {
+                let name = tool.get_str("name").filter(|name| !name.is_empty())?;
+                Some((appid.parse().ok()?,name.to_string()))
+            })
+            .collect()
+        )
+        .unwrap_or_default()
+    )
+}
+
+// Folders searched for custom tools such as GE-Proton, each holding one subfolder per tool
+pub fn compat_tool_dirs(steam_path: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![steam_path.join("compatibilitytools.d")];
+
+    if let Ok(extra) = env::var("STEAM_EXTRA_COMPAT_TOOLS_PATHS") {
+        dirs.extend(env::split_paths(&extra));
+    }
+
+    dirs.push(PathBuf::from("/usr/share/steam/compatibilitytools.d"));
+    dirs.push(PathBuf::from("/usr/local/share/steam/compatibilitytools.d"));
+    dirs
+}
+
+// Reads every `<dir>/<tool>/compatibilitytool.vdf`
+pub fn custom_compat_tools(dirs: &[PathBuf]) -> Vec<CompatTool> {
+    let mut tools = Vec::new();
+
+    for tool_dir in dirs.iter().filter_map(|dir| fs::read_dir(dir).ok()).flatten().flatten().map(|entry| entry.path()) {
+        let Ok(parsed) = vdf::load(&tool_dir.join("compatibilitytool.vdf")) else {
+            continue
+        };
+
+        let Some(compat_tools) = parsed.get_path("compatibilitytools/compat_tools") else {
+            continue
+        };
+
+        for (name,tool) in compat_tools.entries() {
+            tools.push(CompatTool {
+                name: name.clone(),
+                display_name: tool.get_str("display_name").unwrap_or(name).to_string(),
+                path: tool_dir.join(tool.get_str("install_path").unwrap_or("."))
+            });
+        }
+    }
+
+    tools
+}
+
+// Official tools (Proton, Steam Linux Runtime) are apps, so resolve the name to an appid and find its install
+pub fn official_compat_tool(steam_path: &Path,folders: &[LibraryFolder],name: &str) -> Option<CompatTool> {
+    let (kv,_,_) = appinfo::app_info_kv(steam_path,STEAMPLAY_MANIFESTS_APPID).ok()?;
+
+    let tool = kv.get_path("appinfo/extended/compat_tools")?.get(name)?;
+    let appid = tool.get_i64("appid")? as u32;
+    let manifest = appmanifest::app_manifest(folders,appid).ok()?;
+
+    Some(CompatTool {
+        name: name.to_string(),
+        display_name: tool
+            .get_str("display_name")
+            .map(str::to_string)
+            .unwrap_or(manifest.name),
+        path: PathBuf::from(manifest.install_path)
+    })
+}
+
+pub fn find_compat_tool(steam_path: &Path,folders: &[LibraryFolder],name: &str) -> Option<CompatTool> {
+    custom_compat_tools(&compat_tool_dirs(steam_path))
+        .into_iter()
+        .find(|tool| tool.name == name)
+        .or_else(|| official_compat_tool(steam_path,folders,name))
+}
+
+pub fn compat_info(steam_path: &Path,appid: u32) -> error::Result<CompatInfo> {
+    let folders = library::library_folders(steam_path)?;
+    let mapping = compat_tool_mapping(steam_path)?;
+
+    // Steam creates the prefix in the library holding the game
+    let prefix = library::find_app_library(&folders,appid)
+        .map(LibraryFolder::steamapps)
+        .unwrap_or_else(|| steam_path.join("steamapps"))
+        .join("compatdata")
+        .join(appid.to_string())
+        .join("pfx");
+
+    let prefix_exists = prefix.is_dir();
+
+    // The global default only applies to games without a native build, which is only known once a prefix exists
+    let (tool_name,is_default_tool) = match (mapping.get(&appid),mapping.get(&DEFAULT_MAPPING)) {
+        (Some(name),_) => (name.clone(),false),
+        (None,Some(name)) if prefix_exists => (name.clone(),true),
+        _ => ("".to_string(),false)
+    };
+
+    let tool = if tool_name.is_empty() {
+        None
+    } else {
+        find_compat_tool(steam_path,&folders,&tool_name)
+    };
+
+    Ok(CompatInfo {
+        appid,
+        tool_display_name: tool
+            .as_ref()
+            .map(|tool| tool.display_name.clone())
+            .unwrap_or_else(|| tool_name.clone()),
+        tool_path: tool
+            .map(|tool| tool.path.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        tool_name,
+        is_default_tool,
+        prefix_path: prefix.to_string_lossy().into_owned(),
+        prefix_exists
+    })
+}
+
+#[napi]
+pub fn get_compat_info(appid: u32) -> error::Result<CompatInfo> {
+    compat_info(&library::steam_root()?,appid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn reads_tool_mapping() {
+        let mapping = compat_tool_mapping(&fixture("compat")).unwrap();
+
+        assert_eq!(mapping.len(),2);
+        assert_eq!(mapping[&DEFAULT_MAPPING],"proton_experimental");
+        assert_eq!(mapping[&440],"GE-Proton9-7");
+        assert!(compat_tool_mapping(&fixture("missing")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn reads_custom_tools() {
+        let steam_path = fixture("compat");
+        let tools = custom_compat_tools(&[steam_path.join("compatibilitytools.d")]);
+
+        assert_eq!(tools,[CompatTool {
+            name: "GE-Proton9-7".to_string(),
+            display_name: "GE-Proton 9-7".to_string(),
+            path: steam_path.join("compatibilitytools.d").join("GE-Proton9-7").join(".")
+        }]);
+    }
+
+    #[test]
+    fn resolves_per_app_tools() {
+        let steam_path = fixture("compat");
+        let info = compat_info(&steam_path,440).unwrap();
+
+        assert_eq!(info.tool_name,"GE-Proton9-7");
+        assert_eq!(info.tool_display_name,"GE-Proton 9-7");
+        assert_eq!(info.tool_path,steam_path.join("compatibilitytools.d").join("GE-Proton9-7").join(".").to_string_lossy());
+        assert!(!info.is_default_tool);
+        assert_eq!(info.prefix_path,steam_path.join("steamapps").join("compatdata").join("440").join("pfx").to_string_lossy());
+        assert!(!info.prefix_exists);
+    }
+
+    #[test]
+    fn default_tool_needs_a_prefix() {
+        let steam_path = fixture("compat");
+
+        // Not installed, so only the name is known
+        let info = compat_info(&steam_path,1091500).unwrap();
+        assert_eq!((info.tool_name.as_str(),info.tool_display_name.as_str(),info.tool_path.as_str()),("proton_experimental","proton_experimental",""));
+        assert!(info.is_default_tool && info.prefix_exists);
+
+        for appid in [730,620] {
+            let info = compat_info(&steam_path,appid).unwrap();
+            assert!(info.tool_name.is_empty() && !info.is_default_tool && !info.prefix_exists,"{}",appid);
+        }
+    }
+
+    #[test]
+    fn missing_config_means_no_tool() {
+        let steam = tempfile::tempdir().unwrap();
+        fs::create_dir_all(steam.path().join("steamapps")).unwrap();
+        fs::copy(fixture("compat/steamapps/libraryfolders.vdf"),steam.path().join("steamapps").join("libraryfolders.vdf")).unwrap();
+
+        let info = compat_info(steam.path(),440).unwrap();
+
+        assert!(info.tool_name.is_empty() && info.tool_path.is_empty());
+        assert_eq!(info.prefix_path,steam.path().join("steamapps").join("compatdata").join("440").join("pfx").to_string_lossy());
+    }
+}
@@ -0,0 +1,489 @@
+use napi::bindgen_prelude::{Buffer,Either};
+use napi_derive::napi;
+use std::{fs,path::Path};
+use ab_glyph::{Font,FontRef,PxScale,ScaleFont,point};
+use image::{DynamicImage,Rgba,RgbaImage,imageops::{self,FilterType}};
+use crate::{encode::{self,EncodeOptions,ImageFormat},error::{self,ErrorCode}};
+
+// DejaVu Sans, see `fonts/DejaVuSans-LICENSE.txt`
+static CAPTION_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+
+const DEFAULT_CAPTION_SIZE: f64 = 18.0;
+// Bounds the glyph outlines, the caption image itself is never larger than the screenshot
+const MAX_CAPTION_SIZE: f64 = 256.0;
+const DEFAULT_CAPTION_COLOR: [u8;4] = [255,255,255,255];
+const DEFAULT_CAPTION_BACKGROUND: [u8;4] = [0,0,0,160];
+
+#[napi(object)]
+#[derive(Clone,Debug,Default)]
+pub struct Caption {
+    /// Drawn top to bottom, e.g. game name, achievement title and unlock date
+    pub lines: Vec<String>,
+    /// Font size in pixels, at most 256. Defaults to 18
+    pub size: Option<f64>,
+    /// `#RRGGBB` or `#RRGGBBAA`. Defaults to white
+    pub color: Option<String>,
+    /// `#RRGGBB` or `#RRGGBBAA`. Defaults to translucent black
+    pub background: Option<String>
+}
+
+#[napi(object)]
+#[derive(Clone,Debug,Default)]
+pub struct CompositeOptions {
+    /// `topleft`, `top`, `topright`, `left`, `center`, `right`, `bottomleft`, `bottom`, `bottomright` (default) or `custom`
+    pub anchor: Option<String>,
+    /// Margin from the anchored edge, or the absolute position when `anchor` is `custom`
+    pub offset_x: Option<i32>,
+    pub offset_y: Option<i32>,
+    /// Applied to the notification before it is placed, which must still fit inside the screenshot. Defaults to 1
+    pub scale: Option<f64>,
+    /// 0-1. Defaults to 1
+    pub opacity: Option<f64>,
+    /// Drawn below the notification, or above it when there is no room below
+    pub caption: Option<Caption>,
+    pub output: Option<EncodeOptions>,
+    /// Also writes the encoded image here
+    pub path: Option<String>
+}
+
+#[napi(object)]
+pub struct CompositeResult {
+    pub data: Buffer,
+    /// `png`, `jpeg` or `webp`
+    pub format: String,
+    pub width: u32,
+    pub height: u32
+}
+
+pub fn parse_color(color: &str) -> error::Result<[u8;4]> {
+    let invalid = || error::err(ErrorCode::InvalidArgument,format!("Invalid color \"{}\", expected #RRGGBB or #RRGGBBAA",color));
+    let hex = color.trim().strip_prefix('#').ok_or_else(invalid)?;
+
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(invalid())
+    }
+
+    let mut rgba = [255u8;4];
+
+    for (i,channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+        *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2],16).map_err(|_| invalid())?;
+    }
+
+    Ok(rgba)
+}
+
+// Straight-alpha "over", with `src`'s alpha multiplied by `opacity`
+fn blend_pixel(dst: &mut Rgba<u8>,src: [u8;4],opacity: f32) {
+    let src_a = src[3] as f32 / 255.0 * opacity;
+
+    if src_a <= 0.0 {
+        return
+    }
+
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+
+    for i in 0..3 {
+        let channel = (src[i] as f32 * src_a + dst[i] as f32 * dst_a * (1.0 - src_a)) / out_a;
+        dst[i] = channel.round().clamp(0.0,255.0) as u8;
+    }
+
+    dst[3] = (out_a * 255.0).round() as u8;
+}
+
+// Blends `overlay` with its top left corner at `x`/`y`, clipping anything outside `base`
+pub fn blend(base: &mut RgbaImage,overlay: &RgbaImage,x: i64,y: i64,opacity: f32) {
+    for (ox,oy,pixel) in overlay.enumerate_pixels() {
+        let (bx,by) = (x + ox as i64,y + oy as i64);
+
+        if bx >= 0 && by >= 0 && bx < base.width() as i64 && by < base.height() as i64 {
+            blend_pixel(base.get_pixel_mut(bx as u32,by as u32),pixel.0,opacity);
+        }
+    }
+}
+
+fn fill_rect(base: &mut RgbaImage,x: i64,y: i64,width: u32,height: u32,color: [u8;4]) {
+    for by in y.max(0)..(y + height as i64).min(base.height() as i64) {
+        for bx in x.max(0)..(x + width as i64).min(base.width() as i64) {
+            blend_pixel(base.get_pixel_mut(bx as u32,by as u32),color,1.0);
+        }
+    }
+}
+
+// Top left corner of a `size` image placed inside `bounds`
+pub fn anchor_position(anchor: &str,offset: (i32,i32),bounds: (u32,u32),size: (u32,u32)) -> error::Result<(i64,i64)> {
+    let (offset_x,offset_y) = (offset.0 as i64,offset.1 as i64);
+    let (free_x,free_y) = (bounds.0 as i64 - size.0 as i64,bounds.1 as i64 - size.1 as i64);
+
+    let (horizontal,vertical) = match anchor.to_ascii_lowercase().as_str() {
+        "custom" => return Ok((offset_x,offset_y)),
+        "topleft" => (0,0),
+        "top" => (1,0),
+        "topright" => (2,0),
+        "left" => (0,1),
+        "center" => (1,1),
+        "right" => (2,1),
+        "bottomleft" => (0,2),
+        "bottom" => (1,2),
+        "bottomright" => (2,2),
+        other => return Err(error::err(ErrorCode::InvalidArgument,format!("Unsupported anchor \"{}\"",other)))
+    };
+
+    // Offsets push inwards from the anchored edge, and shift centred images right/down
+    let place = |side: u8,free: i64,offset: i64| match side {
+        0 => offset,
+        1 => free / 2 + offset,
+        _ => free - offset
+    };
+
+    Ok((place(horizontal,free_x,offset_x),place(vertical,free_y,offset_y)))
+}
+
+pub struct CaptionStyle {
+    pub size: f32,
+    pub color: [u8;4],
+    pub background: [u8;4]
+}
+
+impl CaptionStyle {
+    pub fn from_caption(caption: &Caption) -> error::Result<Self> {
+        let size = caption.size.unwrap_or(DEFAULT_CAPTION_SIZE);
+
+        if !(size.is_finite() && size > 0.0 && size <= MAX_CAPTION_SIZE) {
+            return Err(error::err(ErrorCode::InvalidArgument,format!("\"size\" must be greater than 0 and at most {}, got {}",MAX_CAPTION_SIZE,size)))
+        }
+
+        Ok(CaptionStyle {
+            size: size as f32,
+            color: caption.color.as_deref().map(parse_color).transpose()?.unwrap_or(DEFAULT_CAPTION_COLOR),
+            background: caption.background.as_deref().map(parse_color).transpose()?.unwrap_or(DEFAULT_CAPTION_BACKGROUND)
+        })
+    }
+}
+
+// Draws `lines` onto an image sized to fit them, with half the font size as padding, clipped to at most `max`
+pub fn render_caption(lines: &[String],style: &CaptionStyle,max: (u32,u32)) -> error::Result<RgbaImage> {
+    let font = FontRef::try_from_slice(CAPTION_FONT)
+        .map_err(|err| error::err(ErrorCode::DecodeFailed,format!("Failed to load caption font: {}",err)))?;
+
+    let scaled = font.as_scaled(PxScale::from(style.size));
+    let line_height = scaled.ascent() - scaled.descent() + scaled.line_gap();
+    let padding = (style.size / 2.0).round();
+
+    let line_width = |line: &str| {
+        let mut width = 0.0;
+        let mut prev = None;
+
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+
+            if let Some(prev) = prev {
+                width += scaled.kern(prev,id);
+            }
+
+            width += scaled.h_advance(id);
+            prev = Some(id);
+        }
+
+        width
+    };
+
+    let text_width = lines.iter().map(|line| line_width(line)).fold(0.0f32,f32::max);
+    let width = ((text_width + padding * 2.0).ceil() as u32).min(max.0);
+    let height = ((line_height * lines.len() as f32 + padding * 2.0).ceil() as u32).min(max.1);
+
+    let mut img = RgbaImage::from_pixel(width,height,Rgba(style.background));
+
+    for (i,line) in lines.iter().enumerate() {
+        let baseline = padding + scaled.ascent() + line_height * i as f32;
+        let mut caret = padding;
+        let mut prev = None;
+
+        for c in line.chars() {
+            let id = scaled.glyph_id(c);
+
+            if let Some(prev) = prev {
+                caret += scaled.kern(prev,id);
+            }
+
+            if let Some(outline) = font.outline_glyph(id.with_scale_and_position(style.size,point(caret,baseline))) {
+                let bounds = outline.px_bounds();
+
+                outline.draw(|gx,gy,coverage| {
+                    let (x,y) = (bounds.min.x as i64 + gx as i64,bounds.min.y as i64 + gy as i64);
+
+                    if x >= 0 && y >= 0 && x < width as i64 && y < height as i64 {
+                        blend_pixel(img.get_pixel_mut(x as u32,y as u32),style.color,coverage.clamp(0.0,1.0));
+                    }
+                });
+            }
+
+            caret += scaled.h_advance(id);
+            prev = Some(id);
+        }
+    }
+
+    Ok(img)
+}
+
+// The background is painted straight onto the screenshot so a translucent box isn't blended twice
+fn draw_caption(base: &mut RgbaImage,caption: &Caption,x: i64,y: i64,below: i64) -> error::Result<()> {
+    let style = CaptionStyle::from_caption(caption)?;
+    let text = render_caption(&caption.lines,&CaptionStyle { background: [0;4], ..style },base.dimensions())?;
+
+    let top = match below + text.height() as i64 <= base.height() as i64 {
+        true => below,
+        false => y - text.height() as i64
+    };
+
+    fill_rect(base,x,top,text.width(),text.height(),style.background);
+    blend(base,&text,x,top,1.0);
+
+    Ok(())
+}
+
+pub fn composite(mut base: RgbaImage,notification: &DynamicImage,options: &CompositeOptions) -> error::Result<RgbaImage> {
+    let scale = options.scale.unwrap_or(1.0);
+    let opacity = options.opacity.unwrap_or(1.0);
+
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err(error::err(ErrorCode::InvalidArgument,format!("\"scale\" must be greater than 0, got {}",scale)))
+    }
+
+    if !(0.0..=1.0).contains(&opacity) {
+        return Err(error::err(ErrorCode::InvalidArgument,format!("\"opacity\" must be between 0 and 1, got {}",opacity)))
+    }
+
+    let width = (notification.width() as f64 * scale).round().max(1.0);
+    let height = (notification.height() as f64 * scale).round().max(1.0);
+
+    // Anything larger than the screenshot would only be clipped, after a possibly huge resize
+    if width > base.width() as f64 || height > base.height() as f64 {
+        return Err(error::err(ErrorCode::InvalidArgument,format!("The notification is {}x{} at scale {}, larger than the {}x{} screenshot",width,height,scale,base.width(),base.height())))
+    }
+
+    let overlay = match scale == 1.0 {
+        true => notification.to_rgba8(),
+        false => imageops::resize(&notification.to_rgba8(),width as u32,height as u32,FilterType::Lanczos3)
+    };
+
+    let (x,y) = anchor_position(
+        options.anchor.as_deref().unwrap_or("bottomright"),
+        (options.offset_x.unwrap_or(0),options.offset_y.unwrap_or(0)),
+        base.dimensions(),
+        overlay.dimensions()
+    )?;
+
+    blend(&mut base,&overlay,x,y,opacity as f32);
+
+    if let Some(caption) = options.caption.as_ref().filter(|caption| !caption.lines.is_empty()) {
+        draw_caption(&mut base,caption,x,y,y + overlay.height() as i64)?;
+    }
+
+    Ok(base)
+}
+
+fn load_image(input: &Either<String,Buffer>,name: &str) -> error::Result<DynamicImage> {
+    let data = match input {
+        Either::A(path) => {
+            let path = Path::new(path);
+            fs::read(path).map_err(|err| error::io_err(path,err))?
+        },
+        Either::B(buffer) => buffer.to_vec()
+    };
+
+    image::load_from_memory(&data)
+        .map_err(|err| error::err(ErrorCode::DecodeFailed,format!("Failed to decode {}: {}",name,err)))
+}
+
+/// Both images may be a path or an encoded buffer, e.g. from `hdrScreenshotBuffer`
+#[napi]
+pub fn composite_notification(screenshot: Either<String,Buffer>,notification: Either<String,Buffer>,options: Option<CompositeOptions>) -> error::Result<CompositeResult> {
+    let options = options.unwrap_or_default();
+    let format = ImageFormat::from_options(&options.output.clone().unwrap_or_default())?;
+
+    let base = load_image(&screenshot,"screenshot")?.to_rgba8();
+    let img = composite(base,&load_image(&notification,"notification")?,&options)?;
+    let (width,height) = img.dimensions();
+    let data = encode::encode(img,format)?;
+
+    if let Some(path) = &options.path {
+        fs::write(path,&data)
+            .map_err(|err| error::err(ErrorCode::WriteFailed,format!("Failed to write \"{}\": {}",path,err)))?;
+    }
+
+    Ok(CompositeResult {
+        data: data.into(),
+        format: format.name().to_string(),
+        width,
+        height
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn notification() -> DynamicImage {
+        let mut img = RgbaImage::from_pixel(20,10,Rgba([255,255,255,255]));
+
+        for x in 0..20 {
+            img.put_pixel(x,0,Rgba([255,0,0,128]));
+        }
+
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn blends_straight_alpha() {
+        let mut dst = Rgba([0,0,255,255]);
+        blend_pixel(&mut dst,[255,0,0,255],0.5);
+        assert_eq!(dst.0,[128,0,128,255]);
+
+        let mut dst = Rgba([0,0,0,0]);
+        blend_pixel(&mut dst,[255,255,255,128],1.0);
+        assert_eq!(dst.0,[255,255,255,128]);
+
+        let mut dst = Rgba([10,20,30,255]);
+        blend_pixel(&mut dst,[255,255,255,0],1.0);
+        assert_eq!(dst.0,[10,20,30,255]);
+    }
+
+    #[test]
+    fn blend_clips_to_base() {
+        let mut overlay = RgbaImage::from_pixel(2,2,Rgba([255,0,0,255]));
+        overlay.put_pixel(1,1,Rgba([255,0,0,0]));
+
+        let mut base = RgbaImage::from_pixel(4,4,Rgba([0,0,255,255]));
+        blend(&mut base,&overlay,3,3,0.5);
+        assert_eq!(base.get_pixel(3,3).0,[128,0,128,255]);
+        assert_eq!(base.get_pixel(2,2).0,[0,0,255,255]);
+
+        let mut base = RgbaImage::from_pixel(4,4,Rgba([0,0,255,255]));
+        blend(&mut base,&overlay,-1,-1,1.0);
+        assert_eq!(base.get_pixel(0,0).0,[0,0,255,255]);
+
+        blend(&mut base,&overlay,0,0,1.0);
+        assert_eq!(base.get_pixel(0,0).0,[255,0,0,255]);
+        assert_eq!(base.get_pixel(1,1).0,[0,0,255,255]);
+    }
+
+    #[test]
+    fn anchors() {
+        let (bounds,size) = ((100,50),(10,10));
+        let expected = [
+            ("topleft",(2,3)),
+            ("top",(47,3)),
+            ("topright",(88,3)),
+            ("left",(2,23)),
+            ("center",(47,23)),
+            ("right",(88,23)),
+            ("bottomleft",(2,37)),
+            ("bottom",(47,37)),
+            ("bottomright",(88,37)),
+            ("BottomRight",(88,37)),
+            ("custom",(2,3))
+        ];
+
+        for (anchor,position) in expected {
+            assert_eq!(anchor_position(anchor,(2,3),bounds,size).unwrap(),position,"{}",anchor);
+        }
+
+        assert_eq!(anchor_position("custom",(-5,7),bounds,size).unwrap(),(-5,7));
+        assert_eq!(anchor_position("bottomright",(0,0),(4,4),(10,10)).unwrap(),(-6,-6));
+        assert_eq!(anchor_position("nowhere",(0,0),bounds,size).unwrap_err().status,ErrorCode::InvalidArgument);
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#ff8000").unwrap(),[255,128,0,255]);
+        assert_eq!(parse_color(" #00000080 ").unwrap(),[0,0,0,128]);
+        assert_eq!(parse_color("#FFFFFF").unwrap(),[255,255,255,255]);
+
+        for invalid in ["ff8000","#ff80","#ff800","#gg8000","#ff8000ff00","#ÿÿÿ"] {
+            assert_eq!(parse_color(invalid).unwrap_err().status,ErrorCode::InvalidArgument,"{}",invalid);
+        }
+    }
+
+    // A caption is left out, as the glyphs depend on the font rasteriser
+    #[test]
+    fn matches_golden_image() {
+        let base = RgbaImage::from_fn(64,48,|x,y| Rgba([(x * 4) as u8,(y * 5) as u8,128,255]));
+        let options = CompositeOptions {
+            anchor: Some("bottomright".to_string()),
+            offset_x: Some(4),
+            offset_y: Some(2),
+            scale: Some(1.5),
+            opacity: Some(0.8),
+            ..Default::default()
+        };
+
+        let img = composite(base,&notification(),&options).unwrap();
+        let golden = image::open(fixture("composite/golden.png")).unwrap().to_rgba8();
+
+        assert_eq!(img.dimensions(),golden.dimensions());
+        assert!(img.as_raw() == golden.as_raw(),"composite differs from tests/fixtures/composite/golden.png");
+    }
+
+    #[test]
+    fn places_caption_above_without_room_below() {
+        let base = RgbaImage::from_pixel(200,100,Rgba([0,0,0,255]));
+        let options = CompositeOptions {
+            scale: Some(2.0),
+            caption: Some(Caption {
+                lines: vec!["Game".to_string(),"Title".to_string()],
+                size: Some(10.0),
+                color: None,
+                background: Some("#0000ff".to_string())
+            }),
+            ..Default::default()
+        };
+
+        let img = composite(base,&notification(),&options).unwrap();
+        assert_eq!(img.get_pixel(199,99).0,[255,255,255,255]);
+        assert_eq!(img.get_pixel(170,90).0,[255,255,255,255]);
+        // The bottom padding row of the caption, directly above the notification
+        assert_eq!(img.get_pixel(160,79).0,[0,0,255,255]);
+        assert_eq!(img.get_pixel(159,80).0,[0,0,0,255]);
+    }
+
+    #[test]
+    fn renders_caption_with_padding() {
+        let style = CaptionStyle::from_caption(&Caption { size: Some(10.0), ..Default::default() }).unwrap();
+        let one = render_caption(&["ab".to_string()],&style,(u32::MAX,u32::MAX)).unwrap();
+        let two = render_caption(&["ab".to_string(),"ab".to_string()],&style,(u32::MAX,u32::MAX)).unwrap();
+
+        assert_eq!(one.width(),two.width());
+        assert!(two.height() > one.height());
+        assert_eq!(one.get_pixel(0,0).0,DEFAULT_CAPTION_BACKGROUND);
+    }
+
+    #[test]
+    fn clips_caption_to_bounds() {
+        let style = CaptionStyle::from_caption(&Caption { size: Some(MAX_CAPTION_SIZE), ..Default::default() }).unwrap();
+        let lines = vec!["a long caption line".repeat(20);50];
+
+        assert_eq!(render_caption(&lines,&style,(40,20)).unwrap().dimensions(),(40,20));
+    }
+
+    #[test]
+    fn rejects_out_of_range_options() {
+        let invalid = |options: CompositeOptions| composite(RgbaImage::new(40,20),&notification(),&options).unwrap_err().status;
+
+        assert_eq!(invalid(CompositeOptions { opacity: Some(2.0), ..Default::default() }),ErrorCode::InvalidArgument);
+        assert_eq!(invalid(CompositeOptions { scale: Some(0.0), ..Default::default() }),ErrorCode::InvalidArgument);
+        assert_eq!(invalid(CompositeOptions { scale: Some(f64::NAN), ..Default::default() }),ErrorCode::InvalidArgument);
+        assert_eq!(invalid(CompositeOptions { scale: Some(1e9), ..Default::default() }),ErrorCode::InvalidArgument);
+        assert_eq!(invalid(CompositeOptions { scale: Some(2.5), ..Default::default() }),ErrorCode::InvalidArgument);
+        assert!(composite(RgbaImage::new(40,20),&notification(),&CompositeOptions { scale: Some(2.0), ..Default::default() }).is_ok());
+
+        let small = composite(RgbaImage::new(4,4),&notification(),&CompositeOptions::default());
+        assert_eq!(small.unwrap_err().status,ErrorCode::InvalidArgument);
+
+        for size in [0.0,-1.0,MAX_CAPTION_SIZE + 1.0,f64::INFINITY] {
+            let caption = Caption { lines: vec!["a".to_string()], size: Some(size), ..Default::default() };
+            assert_eq!(invalid(CompositeOptions { caption: Some(caption), ..Default::default() }),ErrorCode::InvalidArgument);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use napi_derive::napi;
+use std::io::Cursor;
+use image::{codecs::jpeg::JpegEncoder,DynamicImage,ImageOutputFormat,RgbaImage};
+use crate::error::{self,ErrorCode};
+
+const DEFAULT_QUALITY: u32 = 90;
+
+#[napi(object)]
+#[derive(Clone,Debug,Default)]
+pub struct EncodeOptions {
+    /// `png` (default), `jpeg` or `webp`
+    pub format: Option<String>,
+    /// 1-100, used by JPEG and lossy WebP. Defaults to 90
+    pub quality: Option<u32>,
+    /// Encodes WebP losslessly. PNG is always lossless and JPEG never is
+    pub lossless: Option<bool>
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg(u8),
+    WebP { lossless: bool, quality: f32 }
+}
+
+impl ImageFormat {
+    pub fn from_options(options: &EncodeOptions) -> error::Result<Self> {
+        let quality = options.quality.unwrap_or(DEFAULT_QUALITY);
+
+        if !(1..=100).contains(&quality) {
+            return Err(error::err(ErrorCode::InvalidArgument,format!("\"quality\" must be between 1 and 100, got {}",quality)))
+        }
+
+        match options.format.as_deref().unwrap_or("png").to_ascii_lowercase().as_str() {
+            "png" => Ok(ImageFormat::Png),
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg(quality as u8)),
+            "webp" => Ok(ImageFormat::WebP {
+                lossless: options.lossless.unwrap_or(false),
+                quality: quality as f32
+            }),
+            other => Err(error::err(ErrorCode::InvalidArgument,format!("Unsupported image format \"{}\"",other)))
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg(_) => "jpeg",
+            ImageFormat::WebP {..} => "webp"
+        }
+    }
+}
+
+pub fn encode_png(img: RgbaImage) -> error::Result<Vec<u8>> {
+    encode(img,ImageFormat::Png)
+}
+
+pub fn encode(img: RgbaImage,format: ImageFormat) -> error::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let fail = |err: &dyn std::fmt::Display| error::err(ErrorCode::EncodeFailed,format!("Failed to encode {}: {}",format.name(),err));
+
+    match format {
+        ImageFormat::Png => DynamicImage::ImageRgba8(img)
+            .write_to(&mut Cursor::new(&mut buf),ImageOutputFormat::Png)
+            .map_err(|err| fail(&err))?,
+        // JPEG has no alpha channel
+        ImageFormat::Jpeg(quality) => JpegEncoder::new_with_quality(&mut buf,quality)
+            .encode_image(&DynamicImage::ImageRgba8(img).to_rgb8())
+            .map_err(|err| fail(&err))?,
+        ImageFormat::WebP { lossless, quality } => {
+            let encoded = webp::Encoder::from_rgba(img.as_raw(),img.width(),img.height())
+                .encode_simple(lossless,quality)
+                .map_err(|err| fail(&format!("{:?}",err)))?;
+
+            buf.extend_from_slice(&encoded);
+        }
+    }
+
+    Ok(buf)
+}
@@ -0,0 +1,73 @@
+use napi_derive::napi;
+
+/// Errors thrown to JS carry one of these as their `code` property, so callers can branch on `err.code` instead of matching messages
+#[napi(string_enum = "SCREAMING_SNAKE_CASE")]
+#[derive(Debug,PartialEq,Eq)]
+pub enum ErrorCode {
+    SteamNotFound,
+    AppNotFound,
+    FileNotFound,
+    ReadFailed,
+    ParseFailed,
+    KeyNotFound,
+    InvalidArgument,
+    WatchFailed,
+    DecodeFailed,
+    EncodeFailed,
+    MonitorNotFound,
+    CaptureFailed,
+    WriteFailed,
+    WindowNotFound,
+    IconNotFound,
+    DependencyMissing,
+    Cancelled
+}
+
+impl AsRef<str> for ErrorCode {
+    fn as_ref(&self) -> &str {
+        match self {
+            ErrorCode::SteamNotFound => "STEAM_NOT_FOUND",
+            ErrorCode::AppNotFound => "APP_NOT_FOUND",
+            ErrorCode::FileNotFound => "FILE_NOT_FOUND",
+            ErrorCode::ReadFailed => "READ_FAILED",
+            ErrorCode::ParseFailed => "PARSE_FAILED",
+            ErrorCode::KeyNotFound => "KEY_NOT_FOUND",
+            ErrorCode::InvalidArgument => "INVALID_ARGUMENT",
+            ErrorCode::WatchFailed => "WATCH_FAILED",
+            ErrorCode::DecodeFailed => "DECODE_FAILED",
+            ErrorCode::EncodeFailed => "ENCODE_FAILED",
+            ErrorCode::MonitorNotFound => "MONITOR_NOT_FOUND",
+            ErrorCode::CaptureFailed => "CAPTURE_FAILED",
+            ErrorCode::WriteFailed => "WRITE_FAILED",
+            ErrorCode::WindowNotFound => "WINDOW_NOT_FOUND",
+            ErrorCode::IconNotFound => "ICON_NOT_FOUND",
+            ErrorCode::DependencyMissing => "DEPENDENCY_MISSING",
+            ErrorCode::Cancelled => "CANCELLED"
+        }
+    }
+}
+
+pub type Error = napi::Error<ErrorCode>;
+pub type Result<T> = napi::Result<T,ErrorCode>;
+
+pub fn err<R: ToString>(code: ErrorCode,reason: R) -> Error {
+    napi::Error::new(code,reason)
+}
+
+// Writes the error to the log before it is thrown, for exports that used to log their failure strings
+pub fn logged(err: Error) -> Error {
+    extern_log::error!("{}",err.reason);
+    err
+}
+
+pub fn io_err(path: &std::path::Path,err: std::io::Error) -> Error {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => self::err(ErrorCode::FileNotFound,format!("\"{}\" does not exist",path.display())),
+        _ => self::err(ErrorCode::ReadFailed,format!("Failed to read \"{}\": {}",path.display(),err))
+    }
+}
+
+// Keeps the code when an error has to pass through a napi `Status` error, e.g. out of `Task::resolve`
+pub fn to_js(env: napi::Env,err: Error) -> napi::Error {
+    napi::Error::from(napi::JsError::from(err).into_unknown(env))
+}
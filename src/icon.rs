@@ -0,0 +1,416 @@
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use std::{fs,path::Path};
+use image::{imageops::{self,FilterType},DynamicImage,RgbaImage};
+use crate::{encode,error::{self,ErrorCode}};
+
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+// `resize_icon` allocates a `size`x`size` canvas, so the size JS asks for is bounded
+const MAX_ICON_SIZE: u32 = 1024;
+
+// Offsets come from the file itself, so reads past the end (or past `usize::MAX`) give `None`
+fn le_u16(data: &[u8],pos: usize) -> Option<u16> {
+    data.get(pos..pos.checked_add(2)?).map(|b| u16::from_le_bytes([b[0],b[1]]))
+}
+
+fn le_u32(data: &[u8],pos: usize) -> Option<u32> {
+    data.get(pos..pos.checked_add(4)?).map(|b| u32::from_le_bytes([b[0],b[1],b[2],b[3]]))
+}
+
+// Windows `DisplayIcon` values may be quoted and carry an icon index, e.g. `"C:\Game\game.exe",0`.
+// A negative index is a resource id rather than a position
+pub fn split_icon_path(path: &str) -> (&str,Option<i32>) {
+    let path = path.trim();
+
+    let (path,index) = match path.rsplit_once(',') {
+        Some((file,index)) => match index.trim().parse::<i32>() {
+            Ok(index) => (file,Some(index)),
+            Err(_) => (path,None)
+        },
+        None => (path,None)
+    };
+
+    (path.trim().trim_matches('"'),index)
+}
+
+// One entry of an ICO directory (`ICONDIRENTRY`), or of a PE icon group (`GRPICONDIRENTRY`)
+struct IconEntry {
+    width: u32,
+    height: u32,
+    bit_count: u16,
+    data: Vec<u8>
+}
+
+impl IconEntry {
+    fn size(&self) -> u32 {
+        self.width.min(self.height)
+    }
+}
+
+// 0 in a directory entry means 256px
+fn dir_size(val: u8) -> u32 {
+    if val == 0 { 256 } else { val as u32 }
+}
+
+fn ico_entries(data: &[u8]) -> Option<Vec<IconEntry>> {
+    if le_u16(data,0)? != 0 || le_u16(data,2)? != 1 {
+        return None
+    }
+
+    (0..le_u16(data,4)? as usize)
+        .map(|idx| {
+            let entry = 6 + idx * 16;
+            let len = le_u32(data,entry + 8)? as usize;
+            let offset = le_u32(data,entry + 12)? as usize;
+
+            Some(IconEntry {
+                width: dir_size(*data.get(entry)?),
+                height: dir_size(*data.get(entry + 1)?),
+                bit_count: le_u16(data,entry + 6)?,
+                data: data.get(offset..offset.checked_add(len)?)?.to_vec()
+            })
+        })
+        .collect()
+}
+
+// The smallest frame at or above `size`, so downscaling stays sharp, otherwise the largest.
+// Ties go to the higher colour depth
+fn best_entry(entries: Vec<IconEntry>,size: u32) -> Option<IconEntry> {
+    let rank = |entry: &IconEntry| (
+        entry.size() >= size,
+        if entry.size() >= size { u32::MAX - entry.size() } else { entry.size() },
+        entry.bit_count
+    );
+
+    entries.into_iter().max_by_key(rank)
+}
+
+// Rebuilds a single-frame ICO, so the decoder reads the chosen frame rather than its own pick
+fn single_frame_ico(entry: &IconEntry) -> Vec<u8> {
+    let mut ico = Vec::with_capacity(22 + entry.data.len());
+    let dim = |val: u32| if val >= 256 { 0 } else { val as u8 };
+
+    ico.extend_from_slice(&[0,0,1,0,1,0]);
+    ico.extend_from_slice(&[dim(entry.width),dim(entry.height),0,0]);
+    ico.extend_from_slice(&1u16.to_le_bytes());
+    ico.extend_from_slice(&entry.bit_count.to_le_bytes());
+    ico.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+    ico.extend_from_slice(&22u32.to_le_bytes());
+    ico.extend_from_slice(&entry.data);
+    ico
+}
+
+// Minimal PE resource reader, enough to pull `RT_GROUP_ICON`/`RT_ICON` out of an .exe or .dll
+struct PeResources<'a> {
+    data: &'a [u8],
+    // (virtual address, virtual size, raw data offset) of each section
+    sections: Vec<(u32,u32,u32)>,
+    base: usize
+}
+
+impl<'a> PeResources<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.get(..2)? != b"MZ" {
+            return None
+        }
+
+        let pe = le_u32(data,0x3C)? as usize;
+
+        if data.get(pe..pe.checked_add(4)?)? != b"PE\0\0" {
+            return None
+        }
+
+        let section_count = le_u16(data,pe + 6)? as usize;
+        let optional = pe + 24;
+        let optional_size = le_u16(data,pe + 20)? as usize;
+
+        // The data directories start later in PE32+ (64-bit) images
+        let directories = match le_u16(data,optional)? {
+            0x10b => optional + 96,
+            0x20b => optional + 112,
+            _ => return None
+        };
+
+        let rsrc_rva = le_u32(data,directories + 2 * 8)?;
+
+        if rsrc_rva == 0 {
+            return None
+        }
+
+        let sections = (0..section_count)
+            .map(|idx| {
+                let section = optional + optional_size + idx * 40;
+                let virtual_size = le_u32(data,section + 8)?.max(le_u32(data,section + 16)?);
+                Some((le_u32(data,section + 12)?,virtual_size,le_u32(data,section + 20)?))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let mut resources = PeResources { data, sections, base: 0 };
+        resources.base = resources.offset(rsrc_rva)?;
+        Some(resources)
+    }
+
+    fn offset(&self,rva: u32) -> Option<usize> {
+        self.sections
+            .iter()
+            .find(|&&(va,size,_)| rva >= va && rva - va < size)
+            .and_then(|&(va,_,raw)| raw.checked_add(rva - va))
+            .map(|offset| offset as usize)
+    }
+
+    // Offsets inside the resource section, relative to its start
+    fn resource(&self,offset: u32) -> Option<usize> {
+        self.base.checked_add((offset & 0x7FFFFFFF) as usize)
+    }
+
+    // (id, offset) of each entry in a resource directory. Named entries get `None`
+    fn entries(&self,dir: usize) -> Option<Vec<(Option<u32>,u32)>> {
+        let count = le_u16(self.data,dir.checked_add(12)?)? as usize + le_u16(self.data,dir.checked_add(14)?)? as usize;
+
+        (0..count)
+            .map(|idx| {
+                let entry = dir.checked_add(16 + idx * 8)?;
+                let name = le_u32(self.data,entry)?;
+                let id = if name & 0x80000000 == 0 { Some(name) } else { None };
+                Some((id,le_u32(self.data,entry + 4)?))
+            })
+            .collect()
+    }
+
+    // Follows the first entry at each level (usually the only language) down to the data
+    fn leaf(&self,mut offset: u32) -> Option<&'a [u8]> {
+        for _ in 0..4 {
+            if offset & 0x80000000 == 0 {
+                let entry = self.resource(offset)?;
+                let start = self.offset(le_u32(self.data,entry)?)?;
+                let len = le_u32(self.data,entry + 4)? as usize;
+                return self.data.get(start..start.checked_add(len)?)
+            }
+
+            offset = self.entries(self.resource(offset)?)?.first()?.1;
+        }
+
+        None
+    }
+
+    fn of_type(&self,res_type: u32) -> Option<Vec<(Option<u32>,u32)>> {
+        let (_,offset) = self
+            .entries(self.base)?
+            .into_iter()
+            .find(|(id,_)| *id == Some(res_type))?;
+
+        self.entries(self.resource(offset)?)
+    }
+
+    fn icon_group(&self,index: Option<i32>) -> Option<Vec<IconEntry>> {
+        let groups = self.of_type(RT_GROUP_ICON)?;
+
+        let (_,group) = match index {
+            Some(id) if id < 0 => groups.into_iter().find(|(group_id,_)| *group_id == Some(id.unsigned_abs()))?,
+            Some(idx) => groups.into_iter().nth(idx as usize)?,
+            None => groups.into_iter().next()?
+        };
+
+        let group = self.leaf(group)?;
+        let icons = self.of_type(RT_ICON)?;
+
+        (0..le_u16(group,4)? as usize)
+            .map(|idx| {
+                let entry = 6 + idx * 14;
+                let id = le_u16(group,entry + 12)? as u32;
+                let (_,icon) = icons.iter().find(|(icon_id,_)| *icon_id == Some(id))?;
+
+                Some(IconEntry {
+                    width: dir_size(*group.get(entry)?),
+                    height: dir_size(*group.get(entry + 1)?),
+                    bit_count: le_u16(group,entry + 6)?,
+                    data: self.leaf(*icon)?.to_vec()
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn decode_icon(data: &[u8],index: Option<i32>,size: u32) -> error::Result<DynamicImage> {
+    let frame = if data.starts_with(b"MZ") {
+        let entries = PeResources::parse(data)
+            .and_then(|resources| resources.icon_group(index))
+            .ok_or_else(|| error::err(ErrorCode::DecodeFailed,"No icon resources found in executable"))?;
+
+        best_entry(entries,size).map(|entry| single_frame_ico(&entry))
+    } else {
+        ico_entries(data)
+            .and_then(|entries| best_entry(entries,size))
+            .map(|entry| single_frame_ico(&entry))
+    };
+
+    image::load_from_memory(frame.as_deref().unwrap_or(data))
+        .map_err(|err| error::err(ErrorCode::DecodeFailed,format!("Failed to decode icon: {}",err)))
+}
+
+// Fits the image inside a transparent `size`x`size` square, keeping its aspect ratio
+pub fn resize_icon(img: &DynamicImage,size: u32) -> RgbaImage {
+    if img.width() == size && img.height() == size {
+        return img.to_rgba8()
+    }
+
+    let resized = img.resize(size,size,FilterType::Lanczos3).to_rgba8();
+    let mut canvas = RgbaImage::new(size,size);
+    let x = (size - resized.width()) / 2;
+    let y = (size - resized.height()) / 2;
+
+    imageops::overlay(&mut canvas,&resized,x as i64,y as i64);
+    canvas
+}
+
+pub fn icon_image(path: &str,size: u32) -> error::Result<Vec<u8>> {
+    if size == 0 || size > MAX_ICON_SIZE {
+        return Err(error::err(ErrorCode::InvalidArgument,format!("\"size\" must be between 1 and {}, got {}",MAX_ICON_SIZE,size)))
+    }
+
+    let (path,index) = split_icon_path(path);
+    let path = Path::new(path);
+    let data = fs::read(path).map_err(|err| error::io_err(path,err))?;
+
+    let img = decode_icon(&data,index,size)
+        .map_err(|err| error::err(err.status,format!("\"{}\": {}",path.display(),err.reason)))?;
+
+    encode::encode_png(resize_icon(&img,size))
+}
+
+/// Accepts the paths returned by `getHqIcon`/`getAppArtwork`, including `DisplayIcon` values with an icon index.
+/// `size` is at most 1024
+#[napi]
+pub fn get_icon_image(path: String,size: u32) -> error::Result<Buffer> {
+    icon_image(&path,size).map(Buffer::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn read(name: &str) -> Vec<u8> {
+        fs::read(fixture(&format!("icons/{}",name))).unwrap()
+    }
+
+    fn entry(size: u32,bit_count: u16) -> IconEntry {
+        IconEntry {
+            width: size,
+            height: size,
+            bit_count,
+            data: Vec::new()
+        }
+    }
+
+    fn sizes(entries: &[IconEntry]) -> Vec<(u32,u16)> {
+        entries.iter().map(|entry| (entry.size(),entry.bit_count)).collect()
+    }
+
+    #[test]
+    fn splits_display_icon_values() {
+        assert_eq!(split_icon_path("\"C:\\Game\\game.exe\",0"),("C:\\Game\\game.exe",Some(0)));
+        assert_eq!(split_icon_path("C:\\Game\\game.dll, -101"),("C:\\Game\\game.dll",Some(-101)));
+        assert_eq!(split_icon_path(" \"C:\\Game\\game.ico\" "),("C:\\Game\\game.ico",None));
+        assert_eq!(split_icon_path("/games/a,b/icon.png"),("/games/a,b/icon.png",None));
+    }
+
+    #[test]
+    fn picks_best_entry() {
+        let entries = || vec![entry(16,32),entry(32,8),entry(32,32),entry(256,32)];
+        let best = |size| best_entry(entries(),size).map(|entry| (entry.size(),entry.bit_count));
+
+        assert_eq!(best(16),Some((16,32)));
+        assert_eq!(best(24),Some((32,32)));
+        assert_eq!(best(48),Some((256,32)));
+        assert_eq!(best(512),Some((256,32)));
+        assert!(best_entry(Vec::new(),32).is_none());
+    }
+
+    #[test]
+    fn reads_ico_directory() {
+        let entries = ico_entries(&read("multi.ico")).unwrap();
+        assert_eq!(sizes(&entries),[(16,32),(32,8),(32,32),(256,32)]);
+
+        let img = decode_icon(&read("multi.ico"),None,24).unwrap();
+        assert_eq!(img.width(),32);
+        assert_eq!(img.to_rgba8().get_pixel(0,0).0,[0,0,255,255]);
+    }
+
+    #[test]
+    fn reads_pe_icon_groups() {
+        let data = read("icons.exe");
+        let resources = PeResources::parse(&data).unwrap();
+        let group = |index| resources.icon_group(index).map(|entries| sizes(&entries));
+
+        // Groups are listed by id, so position 0 is id 5 and position 1 is id 101
+        assert_eq!(group(None),Some(vec![(48,32)]));
+        assert_eq!(group(Some(0)),Some(vec![(48,32)]));
+        assert_eq!(group(Some(1)),Some(vec![(16,32),(32,32)]));
+        assert_eq!(group(Some(-101)),Some(vec![(16,32),(32,32)]));
+        assert_eq!(group(Some(-5)),Some(vec![(48,32)]));
+        assert_eq!(group(Some(2)),None);
+        assert_eq!(group(Some(-7)),None);
+
+        let img = decode_icon(&data,Some(-101),32).unwrap();
+        assert_eq!(img.width(),32);
+        assert_eq!(img.to_rgba8().get_pixel(0,0).0,[0,255,0,255]);
+
+        assert!(PeResources::parse(&read("multi.ico")).is_none());
+    }
+
+    #[test]
+    fn pads_resized_icons() {
+        let img = image::load_from_memory(&read("wide.bmp")).unwrap();
+        let icon = resize_icon(&img,32);
+
+        assert_eq!(icon.dimensions(),(32,32));
+        // 40x20 scales to 32x16, centred between 8px transparent bands
+        assert_eq!(icon.get_pixel(16,3).0[3],0);
+        assert_eq!(icon.get_pixel(16,28).0[3],0);
+        assert_eq!(icon.get_pixel(16,11).0,[0,0,0,255]);
+        assert_eq!(icon.get_pixel(16,20).0,[255,255,255,255]);
+    }
+
+    #[test]
+    fn encodes_icon_image() {
+        let path = format!("\"{}\",-101",fixture("icons/icons.exe").display());
+        let png = image::load_from_memory(&icon_image(&path,48).unwrap()).unwrap();
+
+        assert_eq!((png.width(),png.height()),(48,48));
+        assert_eq!(icon_image(&path,0).err().unwrap().status,ErrorCode::InvalidArgument);
+        assert_eq!(icon_image(&path,MAX_ICON_SIZE + 1).err().unwrap().status,ErrorCode::InvalidArgument);
+        assert_eq!(icon_image(&path,100_000).err().unwrap().status,ErrorCode::InvalidArgument);
+    }
+
+    #[test]
+    fn section_offsets_do_not_overflow() {
+        let resources = PeResources {
+            data: &[],
+            sections: vec![(0xFFFFFF00,0x200,0x10),(0x1000,0x100,0xFFFFFFF0)],
+            base: usize::MAX
+        };
+
+        assert_eq!(resources.offset(0xFFFFFFFF),Some(0x10F));
+        assert_eq!(resources.offset(0xFFFFFEFF),None);
+        assert_eq!(resources.offset(0x1008),Some(0xFFFFFFF8));
+        assert_eq!(resources.offset(0x1010),None);
+        assert_eq!(resources.resource(1),None);
+        assert!(resources.leaf(0x80000000).is_none());
+    }
+
+    #[test]
+    fn survives_corrupt_pe_fields() {
+        let data = read("icons.exe");
+
+        for pos in (0..data.len() - 4).step_by(2) {
+            let mut corrupt = data.clone();
+            corrupt[pos..pos + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+            for index in [None,Some(1),Some(-101)] {
+                let _ = PeResources::parse(&corrupt).and_then(|resources| resources.icon_group(index));
+            }
+        }
+    }
+}
@@ -6,8 +6,33 @@ use napi_derive::napi;
 use keypressrs;
 extern crate log as extern_log;
 use extern_log::{info,error};
+use error::ErrorCode;
 pub mod log;
 pub mod wininfo;
+pub mod steam;
+pub mod error;
+pub mod cache;
+pub mod vdf;
+pub mod library;
+pub mod appmanifest;
+pub mod appinfo;
+pub mod stats;
+pub mod watcher;
+pub mod users;
+pub mod registry;
+pub mod shortcuts;
+pub mod localconfig;
+pub mod artwork;
+pub mod icon;
+pub mod compat;
+pub mod session;
+pub mod encode;
+pub mod capture;
+pub mod wincapture;
+pub mod composite;
+pub use registry::SteamRegistry;
+#[cfg(target_os="linux")]
+pub mod procscan;
 pub use electron_display_resolver::{get_monitors,utils::MonitorInfo};
 
 #[cfg(target_os="windows")]
@@ -24,225 +49,154 @@ pub mod linux {
     pub use keyvalues_parser::{Vdf,Value};
 }
 
+// Files under `tests/fixtures`, shared by every module's tests
+#[cfg(test)]
+pub fn fixture(path: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(path)
+}
+
 #[cfg(target_os="linux")]
-fn get_key_values(value: &keyvalues_parser::Value, key: &str, all: bool) -> Vec<String> {
-    use linux::Value;
-    let mut res = Vec::new();
-
-    match value {
-        Value::Obj(obj) => {
-            for (k, v) in obj.iter() {
-                for item in v {
-                    if k.to_lowercase() == key.to_lowercase() {
-                        if let Value::Str(val) = item {
-                            res.push(val.clone().to_string());
-                            if !all {
-                                return res;
-                            }
-                        }
-                    } else {
-                        res.extend(get_key_values(item, key, all));
-                        if !all && !res.is_empty() {
-                            return res;
-                        }
-                    }
-                }
-            }
-        }
-        _ => {}
-    }
+pub fn get_linux_steam_path() -> String {
+    steam::active_steam_path()
+}
 
-    res
+#[napi]
+pub fn get_steam_path() -> error::Result<String> {
+    library::steam_root().map(|path| path.to_string_lossy().into_owned())
 }
 
-#[cfg(target_os="linux")]
-fn read_vdf(vdf: String, key: &str, all: bool) -> Vec<String> {
-    use linux::{File, Read, Vdf};
+#[napi(object)]
+pub struct AppInfo {
+    pub appid: u32,
+    pub gamename: String,
+    /// Process the appid was found in. Not available on Windows, where it is read from the registry
+    pub pid: Option<u32>,
+    pub ppid: Option<u32>,
+    /// Unix timestamp
+    pub start_time: Option<i64>,
+    /// Most likely the game being played. Always the first entry
+    pub foreground: bool
+}
 
-    let mut contents = String::new();
+fn resolve_app_name(registry: Option<&SteamRegistry>,folders: Option<&[library::LibraryFolder]>,appid: u32) -> String {
+    // Non-Steam games only exist in the user's shortcuts.vdf
+    if shortcuts::is_shortcut_appid(appid) {
+        return library::steam_root()
+            .ok()
+            .and_then(|steam_path| shortcuts::find_shortcut(&steam_path,appid))
+            .map(|shortcut| shortcut.app_name)
+            .unwrap_or_else(|| {
+                error!("Failed to locate shortcut {} in any \"shortcuts.vdf\"",appid);
+                "".to_string()
+            })
+    }
 
-    let mut file = match File::open(&vdf) {
-        Ok(file) => file,
-        Err(err) => {
-            error!("Failed to open \"{}\": {}",vdf,err);
-            return Vec::new();
-        }
-    };
+    if let Some(name) = registry.and_then(|registry| registry.app_name(appid)) {
+        return name
+    }
 
-    if let Err(err) = file.read_to_string(&mut contents) {
-        error!("Failed to read \"{}\": {}",vdf,err);
-        return Vec::new();
+    if let Some(folders) = folders {
+        match appmanifest::app_manifest(folders,appid) {
+            Ok(manifest) if !manifest.name.is_empty() => return manifest.name,
+            Ok(_) => {},
+            Err(err) => error!("Failed to read appmanifest for {}: {}",appid,err)
+        }
     }
 
-    let parsed = match Vdf::parse(&contents) {
-        Ok(parsed) => parsed,
+    // No local manifest (family-shared, launched via shortcut, unmounted library...), so use Steam's appinfo cache instead
+    match library::steam_root().and_then(|steam_path| appinfo::app_info_entry(&steam_path,appid)) {
+        Ok(entry) => entry.name,
         Err(err) => {
-            error!("Failed to parse contents of \"{}\": {}",vdf,err);
-            return Vec::new();
+            error!("Failed to read \"appinfo.vdf\" entry for {}: {}",appid,err);
+            "".to_string()
         }
-    };
-
-    get_key_values(&parsed.value,key,all)
-}
-
-#[cfg(target_os="linux")]
-pub fn get_linux_steam_path() -> String {
-    use linux::home_dir;
-
-    if let Some(home) = home_dir() {
-        let steam_dir = home.join(".steam");
-        let registry_vdf = steam_dir.join("registry.vdf");
-
-        return read_vdf(
-                registry_vdf
-                .to_string_lossy()
-                .into_owned(),
-                "SourceModInstallPath",
-                false
-            )
-            .join("")
-            .replace("/steamapps\\sourcemods","")
     }
-
-    "".to_string()
 }
 
-#[allow(unreachable_code)]
+#[allow(unused_mut)]
 #[napi]
-pub fn get_steam_path() -> String {
+pub fn get_app_info() -> Vec<AppInfo> {
+    let mut appinfo: Vec<AppInfo> = Vec::new();
+
+    let registry = SteamRegistry::open()
+        .map_err(|err| error!("Failed to open Steam registry: {}",err))
+        .ok();
+
     #[cfg(target_os="windows")] {
-        use win32::{RegKey,HKEY_CURRENT_USER,STEAMREGPATH};
-
-        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-        match hkcu.open_subkey(STEAMREGPATH) {
-            Ok(regkey) => return regkey
-                .get_value("SteamPath")
-                .unwrap_or_else(|_| "".to_string()),
-            Err(err) => error!("Failed to open subkey \"{}\": {}",STEAMREGPATH,err)
+        let running_appid = registry
+            .as_ref()
+            .and_then(SteamRegistry::running_appid)
+            .filter(|&appid| !appinfo::is_non_game(library::steam_root().ok().as_deref(),appid));
+
+        if let Some(appid) = running_appid {
+            appinfo.push(AppInfo {
+                appid,
+                gamename: "".to_string(),
+                pid: None,
+                ppid: None,
+                start_time: None,
+                foreground: true
+            });
         }
     }
 
     #[cfg(target_os="linux")] {
-        return get_linux_steam_path()
-    }
-
-    #[cfg(not(any(target_os="windows",target_os="linux")))] {
-        error!("Unsupported OS");
-    }
+        use active_win_pos_rs::get_active_window;
 
-    "".to_string()
-}
+        let scanner = procscan::ProcScanner::default();
+        let steam_path = library::steam_root().ok();
+        let mut apps = scanner.running_apps();
 
-#[napi(object)]
-pub struct AppInfo {
-    pub appid: u32,
-    pub gamename: String
-}
+        apps.retain(|app| !appinfo::is_non_game(steam_path.as_deref(),app.appid));
+        let focused_pid = get_active_window()
+            .ok()
+            .map(|win| win.process_id as u32);
 
-#[allow(unused_assignments)]
-#[napi]
-pub fn get_app_info() -> Vec<AppInfo> {
-    let mut appinfo = Vec::new();
-    let mut appid: u32 = 0;
-    let mut gamename: String = "".to_string();
+        scanner.mark_foreground(&mut apps,focused_pid,registry.as_ref().and_then(SteamRegistry::running_appid));
+        apps.sort_by_key(|app| !app.foreground);
 
-    #[cfg(target_os="windows")] {
-        use win32::{RegKey,HKEY_CURRENT_USER,STEAMREGPATH};
-
-        let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-        appid = match hkcu.open_subkey(STEAMREGPATH) {
-            Ok(regkey) => regkey
-                .get_value("RunningAppID")
-                .unwrap_or_else(|_| 0),
-            Err(err) => {
-                error!("Failed to open subkey \"{}\": {}",STEAMREGPATH,err);
-                0
-            }
-        };
-
-        if appid != 0 {
-            gamename = match hkcu.open_subkey(format!("{}\\Apps\\{}",STEAMREGPATH,appid)) {
-                Ok(regkey) => regkey
-                    .get_value("Name")
-                    .unwrap_or_else(|_| "".to_string()),
-                Err(err) => {
-                    error!("Failed to open subkey \"{}\": {}",STEAMREGPATH,err);
-                    "".to_string()
-                }
-            }
-        }
+        appinfo.extend(apps
+            .into_iter()
+            .map(|app| AppInfo {
+                appid: app.appid,
+                gamename: "".to_string(),
+                pid: Some(app.pid),
+                ppid: Some(app.ppid),
+                start_time: Some(app.start_time),
+                foreground: app.foreground
+            })
+        );
     }
 
-    #[cfg(target_os="linux")] {
-        use linux::{str,Command,Path};
-
-        let cmd = Command::new("sh")
-            .arg("-c")
-            .arg("ps ax | grep -Ev 'grep|Install' | grep -i 'AppID'")
-            .output();
-
-        match cmd {
-            Ok(output) => {
-                let res = str::from_utf8(&output.stdout);
-
-                match res {
-                    Ok(stdout) => {
-                        if !stdout.is_empty() {
-                            for line in stdout.lines() {
-                                if let Some(arg_str) = line.split("AppId=").nth(1) {
-                                    if let Some(end_idx) = arg_str.find(char::is_whitespace) {
-                                        if let Ok(parsed_appid) = arg_str[..end_idx].trim().parse::<u32>() {
-                                            appid = parsed_appid;
-                                            break;
-                                        }
-                                    } else {
-                                        if let Ok(parsed_appid) = arg_str.trim().parse::<u32>() {
-                                            appid = parsed_appid;
-                                            break;
-                                        }
-                                    }
-                                }
-                            }
-                
-                            if appid != 0 {
-                                let steam_path = get_linux_steam_path();
-                                let lib_folders = Path::new(&steam_path)
-                                    .join("steamapps")
-                                    .join("libraryfolders.vdf")
-                                    .to_string_lossy()
-                                    .into_owned();
-
-                                let lib_paths = read_vdf(lib_folders,"path",true);
-                                for lib_path in lib_paths {
-                                    let acf = Path::new(&lib_path)
-                                        .join("steamapps")
-                                        .join(format!("appmanifest_{}.acf",appid));
-
-                                    if acf.exists() {
-                                        gamename = read_vdf(acf.to_string_lossy().into_owned(),"name",false).join("");
-                                        break;
-                                    } else {
-                                        error!("Failed to locate \"appmanifest_{}.acf\" in \"{}\"",appid,lib_path);
-                                    }
-                                }
-                            }
-                        }
-                    },
-                    Err(err) => error!("Failed to parse \"res\": {}",err)
-                }
-            },
-            Err(err) => error!("Failed to execute \"psaux\" command: {}",err)
-        }
-    }
-    
     #[cfg(not(any(target_os="windows",target_os="linux")))] {
         error!("Unsupported OS");
     }
 
-    appinfo.push(AppInfo {
-        appid,
-        gamename
-    });
+    if appinfo.is_empty() {
+        // Nothing running is still reported as a single entry with an appid of 0
+        appinfo.push(AppInfo {
+            appid: 0,
+            gamename: "".to_string(),
+            pid: None,
+            ppid: None,
+            start_time: None,
+            foreground: false
+        });
+
+        return appinfo
+    }
+
+    let folders = library::steam_root()
+        .and_then(|steam_path| library::library_folders(&steam_path))
+        .map_err(|err| error!("Failed to read library folders: {}",err))
+        .ok();
+
+    for app in appinfo.iter_mut() {
+        app.gamename = resolve_app_name(registry.as_ref(),folders.as_deref(),app.appid);
+    }
 
     appinfo
 }
@@ -263,80 +217,57 @@ pub fn press_keys_linux(keys: Vec<String>) {
     }
 }
 
+#[allow(unused_variables)]
 #[napi]
-pub fn get_hq_icon(appid: u32) -> String {
+pub fn get_hq_icon(appid: u32) -> error::Result<String> {
     #[cfg(target_os="windows")] {
         use win32::{RegKey,HKEY_LOCAL_MACHINE,UNINSTALLPATH};
 
         let appdir = format!("{} {}",UNINSTALLPATH,appid);
         let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
 
-        match hklm.open_subkey(appdir) {
-            Ok(regkey) => return regkey
-                .get_value("DisplayIcon")
-                .unwrap_or_else(|_| "".to_string()),
-            Err(err) => error!("Failed to get \"DisplayIcon\": {}",err)
-        }
+        return hklm
+            .open_subkey(appdir)
+            .and_then(|regkey| regkey.get_value::<String,_>("DisplayIcon"))
+            .map_err(|err| error::logged(error::err(ErrorCode::IconNotFound,format!("Failed to get \"DisplayIcon\" for {}: {}",appid,err))))
     }
 
+    // Falls back to `steam/games` and `appcache/librarycache`, as most games never install a hicolor icon.
+    // Only the fallbacks need the Steam root
     #[cfg(target_os = "linux")]
     {
-        use linux::data_local_dir;
-
-        let resolutions = [
-            "256x256",
-            "128x128",
-            "64x64",
-            "32x32",
-            "24x24",
-            "16x16"
-        ];
+        let steam_path = library::steam_root().ok();
+        let icon = artwork::app_artwork(steam_path.as_deref(),artwork::hicolor_dir().as_deref(),appid).icon;
 
-        if let Some(share) = data_local_dir() {
-            let base_dir = share
-                .join("icons")
-                .join("hicolor");
-
-            if base_dir.exists() {
-                for res in &resolutions {
-                    let icon_path = base_dir
-                        .join(res)
-                        .join("apps")
-                        .join(format!("steam_icon_{}.png",appid));
-
-                    if icon_path.exists() {
-                        return icon_path.to_string_lossy().to_string();
-                    } else {
-                        error!("Failed to locate \"{:?}\" in \"{:?}\"",icon_path,base_dir);
-                    }
-                }
-            } else {
-                error!("Failed to locate \"{:?}\"",base_dir);
-            }
-        } else {
-            error!("Failed to locate \"homedir\"");
+        if icon.is_empty() {
+            return Err(error::logged(error::err(ErrorCode::IconNotFound,format!("Failed to locate an icon for {}",appid))))
         }
+
+        Ok(icon)
     }
 
-    "".to_string()
+    #[cfg(not(any(target_os="windows",target_os="linux")))] {
+        Err(error::err(ErrorCode::IconNotFound,"Unsupported OS"))
+    }
 }
 
+/// Succeeds when everything `lib` needs is installed, otherwise the error names the missing dependency
 #[napi]
-pub fn deps_installed(lib: String) -> String {
-    if lib == "keypressrs" {
-        if !keypressrs::deps_installed() {
-            return "xdotool".to_string();
-        };
-    } else if lib == "hdr" {
-        return hdr_deps();
-    } else if lib == "wmctrl" {
-        return wmctrl_deps();
-    }
+pub fn deps_installed(lib: String) -> error::Result<()> {
+    let missing = match lib.as_str() {
+        "keypressrs" => if keypressrs::deps_installed() { None } else { Some("xdotool".to_string()) },
+        "hdr" => hdr_deps(),
+        "wmctrl" => wmctrl_deps(),
+        other => return Err(error::err(ErrorCode::InvalidArgument,format!("Unknown library \"{}\"",other)))
+    };
 
-    "".to_string()
+    match missing {
+        Some(dep) => Err(error::err(ErrorCode::DependencyMissing,format!("\"{}\" required by \"{}\" is not installed",dep,lib))),
+        None => Ok(())
+    }
 }
 
-fn hdr_deps() -> String {
+fn hdr_deps() -> Option<String> {
     #[cfg(target_os="linux")] {
         use linux::*;
 
@@ -355,16 +286,18 @@ fn hdr_deps() -> String {
 
             if !installed {
                 error!("\"{}\" not installed",dep);
-                return dep.to_string()
+                return Some(dep.to_string())
             }
         }
     }
 
-    "".to_string()
+    None
 }
 
 // Note: Requires `sudo apt install libxcb-xfixes0-dev` to compile on Linux
-fn capture_hdr_screenshot(screen: screenshots::Screen,sspath: String,area: Option<(u32,u32,u32,u32)>) -> String {
+/// Returns `sspath` once the image is saved
+#[napi]
+pub fn hdr_screenshot(monitor_id: u32,sspath: String,area: Option<(u32,u32,u32,u32)>) -> error::Result<String> {
     let mode = if let Some(values) = area {
         info!("Area: {:#?}",values);
         "window"
@@ -372,85 +305,28 @@ fn capture_hdr_screenshot(screen: screenshots::Screen,sspath: String,area: Optio
         "screen"
     };
 
-    // Order of elements for `screen.capture_area()` is y/x/w/h
-    let capture = match area {
-        Some((y,x,w,h)) => screen.capture_area(y as i32,x as i32,w,h),
-        None => screen.capture()
-    };
+    let img = capture::find_screen(monitor_id)
+        .and_then(|screen| capture::capture(&screen,area))
+        .map_err(error::logged)?;
 
-    match capture {
-        Ok(img) => {
-            let save = img.save(&sspath);
+    img.save(&sspath)
+        .map_err(|err| error::logged(error::err(ErrorCode::WriteFailed,format!("Failed to save HDR Mode {} image \"{}\": {}",mode,sspath,err))))?;
 
-            if let Err(err) = save {
-                error!("Failed to save HDR Mode {} image: {}",mode,err);
-                return format!("Failed to save HDR Mode {} image: {}",mode,err)
-            }
-
-            return format!("HDR Mode {} image \"{}\" saved successfully",mode,&sspath)
-        },
-        Err(err) => {
-            error!("Failed to capture {}: {}",mode,err);
-            return format!("Failed to capture {}: {}",mode,err)
-        }
-    }
-}
-
-#[napi]
-pub fn hdr_screenshot(monitor_id: u32,sspath: String,area: Option<(u32,u32,u32,u32)>) -> String {
-    use screenshots::Screen;
-
-    let screenshots_displayinfo_id = get_monitors()
-        .ok()
-        .and_then(|monitors| monitors
-            .into_iter()
-            .find(|monitor| monitor.electron_display_id == monitor_id)
-            .map(|monitor| monitor.screenshots_displayinfo_id)
-        );
-
-    let screens = Screen::all();
-
-    match screens {
-        Ok(screens) => {
-            let mut primary = None;
-            
-            for screen in screens {
-                if screenshots_displayinfo_id.is_some_and(|id| screen.display_info.id == id) {
-                    info!("\"screen.display_info.id\" ({}) matched to \"monitor_id\" (electron_display_id: {} | screenshots_displayinfo_id: {}) successfully",screen.display_info.id,monitor_id,screenshots_displayinfo_id.unwrap_or(0));
-                    return capture_hdr_screenshot(screen,sspath,area);
-                }
-
-                if screen.display_info.is_primary {
-                    primary = Some(screen.clone());
-                }
-            }
-
-            if let Some(primary_screen) = primary {
-                error!("No match found for \"monitor_id\" ({}) - fallback to primary monitor",monitor_id);
-                return capture_hdr_screenshot(primary_screen,sspath,area)
-            } else {
-                error!("Failed to locate screen matching \"monitor_id\" ({}), and no primary monitor located",monitor_id);
-                format!("Failed to locate screen matching \"monitor_id\" ({}), and no primary monitor located",monitor_id)
-            }
-        }
-        Err(err) => {
-            error!("Failed to parse monitor list: {}",err);
-            format!("Failed to parse monitor list: {}",err)
-        }
-    }
+    info!("HDR Mode {} image \"{}\" saved successfully",mode,&sspath);
+    Ok(sspath)
 }
 
 #[napi]
-pub fn get_focused_win_path() -> String {
+pub fn get_focused_win_path() -> error::Result<String> {
     use active_win_pos_rs::get_active_window;
 
-    match get_active_window() {
-        Ok(win) => win.process_path.to_string_lossy().to_string(),
-        Err(_) => "".to_string()
-    }
+    // `active-win-pos-rs` gives no cause, and the usual one is a display server it can't query, e.g. Wayland
+    get_active_window()
+        .map(|win| win.process_path.to_string_lossy().to_string())
+        .map_err(|()| error::err(ErrorCode::WindowNotFound,format!("Failed to get the focused window on display server \"{}\"",session::SessionProbe::default().display_server())))
 }
 
-fn wmctrl_deps() -> String {
+fn wmctrl_deps() -> Option<String> {
     #[cfg(target_os="linux")] {
         use linux::*;
         
@@ -461,11 +337,11 @@ fn wmctrl_deps() -> String {
             .unwrap_or(false);
 
         if !installed {
-            return "wmctrl".to_string()
+            return Some("wmctrl".to_string())
         }
     }
 
-    "".to_string()
+    None
 }
 
 #[napi(object)]
@@ -477,20 +353,18 @@ pub struct WinBounds {
 }
 
 #[napi]
-pub fn get_window_bounds(windowtitle: String) -> WinBounds {
+pub fn get_window_bounds(windowtitle: String) -> error::Result<WinBounds> {
     use wininfo::wininfo::get_window_bounds;
 
-    let (x,y,width,height) = match get_window_bounds(&windowtitle) {
-        Some((x,y,width,height)) => (x,y,width,height),
-        None => (0,0,0,0)
-    };
+    let (x,y,width,height) = get_window_bounds(&windowtitle)
+        .ok_or_else(|| error::err(ErrorCode::WindowNotFound,format!("Failed to locate window \"{}\"",windowtitle)))?;
 
-    WinBounds {
+    Ok(WinBounds {
         width,
         height,
         x,
         y
-    }
+    })
 }
 
 #[napi(object)]
@@ -511,11 +385,11 @@ pub struct DisplayObject {
 }
 
 #[napi]
-pub fn get_all_displays() -> napi::Result<Vec<DisplayObject>> {
+pub fn get_all_displays() -> error::Result<Vec<DisplayObject>> {
     use display_info;
 
     let info = display_info::DisplayInfo::all()
-        .map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        .map_err(|e| error::err(ErrorCode::MonitorNotFound,format!("Failed to list displays: {}",e)))?;
 
     Ok(info
         .into_iter()
@@ -0,0 +1,163 @@
+use napi_derive::napi;
+use std::{collections::HashMap,path::{Path,PathBuf}};
+use crate::{error::{self,ErrorCode},steam,vdf::{self,VdfValue}};
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct LibraryFolder {
+    pub index: u32,
+    pub path: String,
+    pub label: String,
+    pub contentid: String,
+    pub totalsize: i64,
+    /// Installed appids mapped to their size on disk in bytes
+    pub apps: HashMap<String,i64>
+}
+
+impl LibraryFolder {
+    pub fn steamapps(&self) -> PathBuf {
+        Path::new(&self.path).join("steamapps")
+    }
+
+    pub fn has_app(&self,appid: u32) -> bool {
+        self.apps.contains_key(&appid.to_string())
+    }
+}
+
+fn parse_library_folder(index: u32,value: &VdfValue) -> Option<LibraryFolder> {
+    match value {
+        // Pre-2021 clients stored a bare path per index
+        VdfValue::Str(path) => Some(LibraryFolder {
+            index,
+            path: path.clone(),
+            label: "".to_string(),
+            contentid: "".to_string(),
+            totalsize: 0,
+            apps: HashMap::new()
+        }),
+        VdfValue::Obj(_) => Some(LibraryFolder {
+            index,
+            path: value.get_str("path")?.to_string(),
+            label: value.get_str("label").unwrap_or_default().to_string(),
+            contentid: value.get_str("contentid").unwrap_or_default().to_string(),
+            totalsize: value.get_i64("totalsize").unwrap_or(0),
+            apps: value
+                .get("apps")
+                .map(|apps| apps
+                    .entries()
+                    .iter()
+                    .map(|(appid,size)| (appid.clone(),size.as_i64().unwrap_or(0)))
+                    .collect()
+                )
+                .unwrap_or_default()
+        }),
+        _ => None
+    }
+}
+
+pub fn library_folders(steam_path: &Path) -> error::Result<Vec<LibraryFolder>> {
+    let vdf_path = steam_path
+        .join("steamapps")
+        .join("libraryfolders.vdf");
+
+    let parsed = vdf::load(&vdf_path)?;
+    let root = parsed
+        .entries()
+        .first()
+        .map(|(_,root)| root)
+        .ok_or_else(|| error::err(ErrorCode::KeyNotFound,format!("\"{}\" is empty",vdf_path.display())))?;
+
+    let mut folders: Vec<LibraryFolder> = root
+        .entries()
+        .iter()
+        .filter_map(|(k,v)| k
+            .parse::<u32>()
+            .ok()
+            .and_then(|index| parse_library_folder(index,v))
+        )
+        .collect();
+
+    // The old format leaves out the Steam root itself, which is always library 0
+    if !folders.iter().any(|folder| folder.index == 0) {
+        folders.insert(0,LibraryFolder {
+            index: 0,
+            path: steam_path.to_string_lossy().into_owned(),
+            label: "".to_string(),
+            contentid: "".to_string(),
+            totalsize: 0,
+            apps: HashMap::new()
+        });
+    }
+
+    Ok(folders)
+}
+
+pub fn steam_root() -> error::Result<PathBuf> {
+    let steam_path = steam::active_steam_path();
+
+    match steam_path.is_empty() {
+        true => Err(error::err(ErrorCode::SteamNotFound,"No Steam installation found")),
+        false => Ok(PathBuf::from(steam_path))
+    }
+}
+
+// Returns the library whose `apps` lists `appid`, falling back to the first library containing its appmanifest
+pub fn find_app_library(folders: &[LibraryFolder],appid: u32) -> Option<&LibraryFolder> {
+    folders
+        .iter()
+        .find(|folder| folder.has_app(appid))
+        .or_else(|| folders
+            .iter()
+            .find(|folder| folder.steamapps().join(format!("appmanifest_{}.acf",appid)).exists())
+        )
+}
+
+#[napi]
+pub fn get_library_folders() -> error::Result<Vec<LibraryFolder>> {
+    library_folders(&steam_root()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn parses_library_folders() {
+        let folders = library_folders(&fixture("steam")).unwrap();
+
+        assert_eq!(folders.len(),2);
+        assert_eq!(folders[0].index,0);
+        assert_eq!(folders[0].path,"/home/user/.local/share/Steam");
+        assert_eq!(folders[0].contentid,"4268959136473296142");
+        assert_eq!(folders[0].apps.get("440"),Some(&28316485421));
+        assert_eq!(folders[1].label,"Games");
+        assert_eq!(folders[1].totalsize,1000204886016);
+        assert!(folders[1].has_app(1091500));
+        assert!(!folders[1].has_app(440));
+    }
+
+    #[test]
+    fn adds_the_steam_root_to_legacy_libraries() {
+        let steam_path = fixture("libraries/legacy");
+        let folders = library_folders(&steam_path).unwrap();
+        let found: Vec<(u32,&str)> = folders.iter().map(|folder| (folder.index,folder.path.as_str())).collect();
+
+        assert_eq!(found,[
+            (0,steam_path.to_str().unwrap()),
+            (1,"/mnt/games/SteamLibrary"),
+            (2,"/media/external/Steam")
+        ]);
+        assert!(folders.iter().all(|folder| folder.apps.is_empty() && folder.label.is_empty()));
+    }
+
+    #[test]
+    fn finds_the_library_of_an_app() {
+        let folders = library_folders(&fixture("steam")).unwrap();
+
+        assert_eq!(find_app_library(&folders,1091500).map(|folder| folder.index),Some(1));
+        assert_eq!(find_app_library(&folders,228980).map(|folder| folder.index),Some(0));
+        assert!(find_app_library(&folders,12).is_none());
+        assert_eq!(library_folders(&fixture("missing")).unwrap_err().status,ErrorCode::FileNotFound);
+    }
+}
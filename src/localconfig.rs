@@ -0,0 +1,140 @@
+use napi_derive::napi;
+use std::{collections::HashMap,fs,path::{Path,PathBuf}};
+use crate::{cache::FileCache,error::{self,ErrorCode},library,vdf};
+
+#[napi(object)]
+#[derive(Clone,Debug,Default)]
+pub struct UserAppConfig {
+    pub appid: u32,
+    /// Unix timestamp
+    pub last_played: i64,
+    /// Total minutes played
+    pub playtime: u32,
+    /// Minutes played in the last two weeks
+    #[napi(js_name = "playtime2wks")]
+    pub playtime2wks: u32,
+    /// Steam only stores `OverlayAppEnable` once changed, and the overlay is enabled by default
+    pub overlay_app_enable: bool,
+    pub launch_options: String
+}
+
+pub fn localconfig_path(steam_path: &Path,accountid: u32) -> PathBuf {
+    steam_path
+        .join("userdata")
+        .join(accountid.to_string())
+        .join("config")
+        .join("localconfig.vdf")
+}
+
+// Playtime and launch options live under `Software/Valve/Steam/apps/<appid>`, the overlay setting under `apps/<appid>`
+fn app_key<'a>(path: &[String],key: &'a str) -> Option<(u32,&'a str)> {
+    let is = |idx: usize,name: &str| path.get(idx).is_some_and(|key| key.eq_ignore_ascii_case(name));
+
+    let appid = match path.len() {
+        6 if is(1,"Software") && is(2,"Valve") && is(3,"Steam") && is(4,"apps") => &path[5],
+        3 if is(1,"apps") => &path[2],
+        _ => return None
+    };
+
+    appid.parse().ok().map(|appid| (appid,key))
+}
+
+// Indexes every app in a single streaming pass, as the file is several MB and mostly holds unrelated settings
+pub fn parse_localconfig(contents: &str) -> error::Result<HashMap<u32,UserAppConfig>> {
+    let mut apps: HashMap<u32,UserAppConfig> = HashMap::new();
+
+    vdf::visit_text(contents,|path,key,val| {
+        let Some((appid,key)) = app_key(path,key) else {
+            return
+        };
+
+        let config = apps.entry(appid).or_insert_with(|| UserAppConfig {
+            appid,
+            overlay_app_enable: true,
+            ..Default::default()
+        });
+
+        let num = || val.trim().parse::<i64>().unwrap_or(0);
+
+        match key.to_ascii_lowercase().as_str() {
+            "lastplayed" => config.last_played = num(),
+            "playtime" => config.playtime = num() as u32,
+            "playtime2wks" => config.playtime2wks = num() as u32,
+            "overlayappenable" => config.overlay_app_enable = num() != 0,
+            "launchoptions" => config.launch_options = val.to_string(),
+            _ => {}
+        }
+    })?;
+
+    Ok(apps)
+}
+
+// The index is kept until localconfig.vdf changes, so repeated lookups (e.g. one per unlock) are cheap
+static CONFIG_CACHE: FileCache<HashMap<u32,UserAppConfig>> = FileCache::new();
+
+fn load_localconfig(path: &Path) -> error::Result<HashMap<u32,UserAppConfig>> {
+    let contents = fs::read_to_string(path).map_err(|err| error::io_err(path,err))?;
+
+    parse_localconfig(&contents)
+        .map_err(|err| error::err(err.status,format!("Failed to parse \"{}\": {}",path.display(),err.reason)))
+}
+
+pub fn user_app_config(steam_path: &Path,accountid: u32,appid: u32) -> error::Result<UserAppConfig> {
+    let path = localconfig_path(steam_path,accountid);
+
+    CONFIG_CACHE.with(&path,load_localconfig,|apps| apps
+        .get(&appid)
+        .cloned()
+        .ok_or_else(|| error::err(ErrorCode::AppNotFound,format!("{} not found in \"{}\"",appid,path.display())))
+    )
+}
+
+#[napi]
+pub fn get_user_app_config(accountid: u32,appid: u32) -> error::Result<UserAppConfig> {
+    user_app_config(&library::steam_root()?,accountid,appid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn reads_playtime_and_launch_options() {
+        let config = user_app_config(&fixture("steam"),12345,440).unwrap();
+
+        assert_eq!(config.last_played,1712345678);
+        assert_eq!(config.playtime,4321);
+        assert_eq!(config.playtime2wks,65);
+        assert_eq!(config.launch_options,"-novid -console +exec \"autoexec.cfg\"");
+        assert!(!config.overlay_app_enable);
+    }
+
+    #[test]
+    fn overlay_defaults_to_enabled() {
+        let config = user_app_config(&fixture("steam"),12345,1091500).unwrap();
+
+        assert_eq!(config.playtime,90);
+        assert_eq!(config.playtime2wks,0);
+        assert!(config.launch_options.is_empty());
+        assert!(config.overlay_app_enable);
+    }
+
+    #[test]
+    fn only_indexes_app_keys() {
+        let contents = fs::read_to_string(fixture("steam/userdata/12345/config/localconfig.vdf")).unwrap();
+        let mut appids: Vec<u32> = parse_localconfig(&contents).unwrap().into_keys().collect();
+        appids.sort_unstable();
+
+        assert_eq!(appids,[440,1091500]);
+        assert_eq!(user_app_config(&fixture("steam"),12345,730).unwrap_err().status,ErrorCode::AppNotFound);
+        assert_eq!(user_app_config(&fixture("steam"),1,440).unwrap_err().status,ErrorCode::FileNotFound);
+    }
+
+    #[test]
+    fn rejects_unbalanced_braces() {
+        for contents in ["\"root\" { \"apps\" { \"440\" { \"Playtime\" \"1\" }","\"root\" { } }","\"root\" { \"key\" }"] {
+            assert_eq!(parse_localconfig(contents).unwrap_err().status,ErrorCode::ParseFailed,"{}",contents);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+use std::{fs,path::{Path,PathBuf}};
+
+// Where a process's appid was found
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum AppIdSource {
+    /// `AppId=<appid>` argument passed to Steam's `reaper`
+    Reaper,
+    /// `SteamAppId`/`SteamGameId` in the process environment
+    Environ
+}
+
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct SteamProcess {
+    pub pid: u32,
+    pub ppid: u32,
+    /// Unix timestamp
+    pub start_time: i64,
+    pub appid: u32,
+    pub source: AppIdSource
+}
+
+// Every process carrying the same appid, e.g. `reaper`, a Proton wrapper and the game itself
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct RunningApp {
+    pub appid: u32,
+    /// The most recently started process, which is the game rather than its launchers
+    pub pid: u32,
+    pub ppid: u32,
+    pub start_time: i64,
+    pub pids: Vec<u32>,
+    pub foreground: bool
+}
+
+// `USER_HZ`, the unit of `starttime` in `/proc/<pid>/stat`. Fixed at 100 on every mainstream architecture
+const CLOCK_TICKS: i64 = 100;
+
+// Scans `/proc` (or a fake tree for tests) for processes launched by Steam
+pub struct ProcScanner {
+    root: PathBuf
+}
+
+impl Default for ProcScanner {
+    fn default() -> Self {
+        ProcScanner::new("/proc")
+    }
+}
+
+// `SteamGameId` of a non-Steam shortcut is a 64-bit game id with the shortcut's appid in the upper half
+fn parse_game_id(val: &str) -> Option<u32> {
+    match val.trim().parse::<u64>().ok()? {
+        0 => None,
+        id if id > u32::MAX as u64 => Some((id >> 32) as u32),
+        id => Some(id as u32)
+    }
+}
+
+fn split_nul(data: &[u8]) -> impl Iterator<Item = String> + '_ {
+    data.split(|&b| b == 0)
+        .filter(|item| !item.is_empty())
+        .map(|item| String::from_utf8_lossy(item).into_owned())
+}
+
+pub fn appid_from_cmdline(cmdline: &[u8]) -> Option<u32> {
+    let args: Vec<String> = split_nul(cmdline).collect();
+
+    // Install scripts run with the game's AppId, but are not the game
+    if args.iter().any(|arg| arg.contains("Install")) {
+        return None
+    }
+
+    args.iter()
+        .find_map(|arg| arg
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("AppId="))
+            .and_then(|_| arg[6..].trim().parse::<u32>().ok())
+        )
+        .filter(|&appid| appid != 0)
+}
+
+pub fn appid_from_environ(environ: &[u8]) -> Option<u32> {
+    let vars: Vec<(String,String)> = split_nul(environ)
+        .filter_map(|var| var
+            .split_once('=')
+            .map(|(k,v)| (k.to_string(),v.to_string()))
+        )
+        .collect();
+
+    let get = |key: &str| vars
+        .iter()
+        .find(|(k,_)| k == key)
+        .and_then(|(_,v)| parse_game_id(v));
+
+    get("SteamAppId").or_else(|| get("SteamGameId"))
+}
+
+impl ProcScanner {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        ProcScanner {
+            root: root.as_ref().to_path_buf()
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn pids(&self) -> Vec<u32> {
+        let mut pids: Vec<u32> = fs::read_dir(&self.root)
+            .map(|entries| entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect()
+            )
+            .unwrap_or_default();
+
+        pids.sort_unstable();
+        pids
+    }
+
+    pub fn read(&self,pid: u32,file: &str) -> Option<Vec<u8>> {
+        fs::read(self.root.join(pid.to_string()).join(file)).ok()
+    }
+
+    // `environ` is only readable for our own processes, which is fine as games run as the same user
+    pub fn process(&self,pid: u32) -> Option<SteamProcess> {
+        let from_cmdline = self
+            .read(pid,"cmdline")
+            .and_then(|cmdline| appid_from_cmdline(&cmdline))
+            .map(|appid| (appid,AppIdSource::Reaper));
+
+        from_cmdline
+            .or_else(|| self
+                .read(pid,"environ")
+                .and_then(|environ| appid_from_environ(&environ))
+                .map(|appid| (appid,AppIdSource::Environ))
+            )
+            .map(|(appid,source)| {
+                let (ppid,start_time) = self.stat(pid).unwrap_or((0,0));
+                SteamProcess { pid, ppid, start_time, appid, source }
+            })
+    }
+
+    pub fn scan(&self) -> Vec<SteamProcess> {
+        self.pids()
+            .into_iter()
+            .filter_map(|pid| self.process(pid))
+            .collect()
+    }
+
+    // Processes running `exe`, matched on `/proc/<pid>/exe` or, for Wine/Proton games, on `argv[0]`
+    pub fn pids_for_exe(&self,exe: &Path) -> Vec<u32> {
+        self.pids()
+            .into_iter()
+            .filter(|&pid| {
+                fs::read_link(self.root.join(pid.to_string()).join("exe")).is_ok_and(|path| path == exe)
+                    || self
+                        .read(pid,"cmdline")
+                        .and_then(|cmdline| split_nul(&cmdline).next())
+                        .is_some_and(|argv0| Path::new(&argv0) == exe)
+            })
+            .collect()
+    }
+
+    fn boot_time(&self) -> Option<i64> {
+        fs::read_to_string(self.root.join("stat"))
+            .ok()?
+            .lines()
+            .find_map(|line| line.strip_prefix("btime "))
+            .and_then(|btime| btime.trim().parse().ok())
+    }
+
+    // Parent pid and start time (Unix timestamp) from `/proc/<pid>/stat`
+    pub fn stat(&self,pid: u32) -> Option<(u32,i64)> {
+        let stat = String::from_utf8(self.read(pid,"stat")?).ok()?;
+
+        // `comm` may contain spaces and parentheses, so fields are counted from the last `)`
+        let fields: Vec<&str> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let ppid = fields.get(1)?.parse().ok()?;
+        let ticks: i64 = fields.get(19)?.parse().ok()?;
+
+        Some((ppid,self.boot_time().unwrap_or(0) + ticks / CLOCK_TICKS))
+    }
+
+    pub fn ppid(&self,pid: u32) -> Option<u32> {
+        self.stat(pid).map(|(ppid,_)| ppid)
+    }
+
+    // Groups processes by appid, newest app first
+    pub fn running_apps(&self) -> Vec<RunningApp> {
+        let mut apps: Vec<RunningApp> = Vec::new();
+
+        for process in self.scan() {
+            match apps.iter_mut().find(|app| app.appid == process.appid) {
+                Some(app) => {
+                    app.pids.push(process.pid);
+
+                    if process.start_time >= app.start_time {
+                        app.pid = process.pid;
+                        app.ppid = process.ppid;
+                        app.start_time = process.start_time;
+                    }
+                },
+                None => apps.push(RunningApp {
+                    appid: process.appid,
+                    pid: process.pid,
+                    ppid: process.ppid,
+                    start_time: process.start_time,
+                    pids: vec![process.pid],
+                    foreground: false
+                })
+            }
+        }
+
+        apps.sort_by_key(|app| std::cmp::Reverse(app.start_time));
+        apps
+    }
+
+    // Index of the app owning `pid` or one of its ancestors
+    pub fn app_for_pid(&self,apps: &[RunningApp],pid: u32) -> Option<usize> {
+        let mut current = pid;
+
+        // Bounded in case of a malformed tree
+        for _ in 0..64 {
+            if let Some(idx) = apps.iter().position(|app| app.pids.contains(&current)) {
+                return Some(idx)
+            }
+
+            current = match self.ppid(current) {
+                Some(ppid) if ppid > 1 => ppid,
+                _ => return None
+            };
+        }
+
+        None
+    }
+
+    // Flags the app owning the focused window, then the one Steam reports as running, then the newest
+    pub fn mark_foreground(&self,apps: &mut [RunningApp],focused_pid: Option<u32>,running_appid: Option<u32>) {
+        let idx = focused_pid
+            .and_then(|pid| self.app_for_pid(apps,pid))
+            .or_else(|| running_appid.and_then(|appid| apps.iter().position(|app| app.appid == appid)))
+            .or(if apps.is_empty() { None } else { Some(0) });
+
+        if let Some(idx) = idx {
+            apps[idx].foreground = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const BOOT_TIME: i64 = 1_700_000_000;
+
+    struct FakeProc(TempDir);
+
+    impl FakeProc {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            fs::write(dir.path().join("stat"),format!("cpu 1 2 3\nbtime {}\n",BOOT_TIME)).unwrap();
+            FakeProc(dir)
+        }
+
+        fn add(&self,pid: u32,comm: &str,ppid: u32,start_ticks: i64,cmdline: &[&str],environ: &[&str]) {
+            let dir = self.0.path().join(pid.to_string());
+            let nul = |items: &[&str]| items.iter().map(|item| format!("{}\0",item)).collect::<String>();
+
+            // Fields after `comm`: state, ppid, then 17 more before `starttime`
+            let mut fields = vec!["0".to_string();40];
+            fields[0] = "S".to_string();
+            fields[1] = ppid.to_string();
+            fields[19] = start_ticks.to_string();
+
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("cmdline"),nul(cmdline)).unwrap();
+            fs::write(dir.join("environ"),nul(environ)).unwrap();
+            fs::write(dir.join("stat"),format!("{} ({}) {}",pid,comm,fields.join(" "))).unwrap();
+        }
+
+        fn scanner(&self) -> ProcScanner {
+            ProcScanner::new(self.0.path())
+        }
+    }
+
+    #[test]
+    fn appid_from_reaper_cmdline() {
+        assert_eq!(appid_from_cmdline(b"reaper\0SteamLaunch\0AppId=440\0--\0hl2.sh\0"),Some(440));
+        assert_eq!(appid_from_cmdline(b"reaper\0appid=440\0"),Some(440));
+        assert_eq!(appid_from_cmdline(b"reaper\0APPID=440\0"),Some(440));
+        assert_eq!(appid_from_cmdline(b"reaper\0AppId=0\0"),None);
+        assert_eq!(appid_from_cmdline(b"reaper\0SteamLaunch\0AppId=440\0--\0InstallScript.sh\0"),None);
+    }
+
+    #[test]
+    fn appid_from_shortcut_environ() {
+        let gameid = ((0x8000_1234u64) << 32) | 0x0200_0000;
+
+        assert_eq!(appid_from_environ(format!("HOME=/home/deck\0SteamGameId={}\0",gameid).as_bytes()),Some(0x8000_1234));
+        assert_eq!(appid_from_environ(b"SteamGameId=730\0SteamAppId=440\0"),Some(440));
+        assert_eq!(appid_from_environ(b"SteamAppId=0\0"),None);
+        assert_eq!(appid_from_environ(b"HOME=/home/deck\0"),None);
+    }
+
+    #[test]
+    fn parses_stat_with_awkward_comm() {
+        let proc = FakeProc::new();
+        proc.add(100,"Game (x64) ) v2",42,12_345,&["game"],&[]);
+
+        assert_eq!(proc.scanner().stat(100),Some((42,BOOT_TIME + 123)));
+        assert_eq!(proc.scanner().stat(101),None);
+    }
+
+    #[test]
+    fn groups_and_orders_running_apps() {
+        let proc = FakeProc::new();
+        proc.add(10,"bash",1,100,&["/bin/bash"],&["HOME=/home/deck"]);
+        proc.add(20,"reaper",10,500,&["reaper","SteamLaunch","AppId=440","--","hl2.sh"],&[]);
+        proc.add(21,"hl2_linux",20,600,&["hl2_linux"],&["SteamAppId=440"]);
+        proc.add(30,"proton",10,700,&["proton"],&["SteamAppId=1493710"]);
+        proc.add(31,"child",21,800,&["child"],&[]);
+
+        let scanner = proc.scanner();
+        let mut apps = scanner.running_apps();
+
+        let summary: Vec<(u32,u32,Vec<u32>)> = apps
+            .iter()
+            .map(|app| (app.appid,app.pid,app.pids.clone()))
+            .collect();
+
+        assert_eq!(summary,[(1493710,30,vec![30]),(440,21,vec![20,21])]);
+        assert_eq!(apps[1].start_time,BOOT_TIME + 6);
+
+        // The focused window's process (or its ancestor) wins over Steam's running app and the newest app
+        scanner.mark_foreground(&mut apps,Some(31),Some(1493710));
+        assert_eq!(apps.iter().map(|app| app.foreground).collect::<Vec<_>>(),[false,true]);
+
+        let mut apps = scanner.running_apps();
+        scanner.mark_foreground(&mut apps,Some(10),Some(440));
+        assert_eq!(apps.iter().map(|app| app.foreground).collect::<Vec<_>>(),[false,true]);
+
+        let mut apps = scanner.running_apps();
+        scanner.mark_foreground(&mut apps,None,None);
+        assert_eq!(apps.iter().map(|app| app.foreground).collect::<Vec<_>>(),[true,false]);
+    }
+
+    #[test]
+    fn finds_pids_for_exe() {
+        let proc = FakeProc::new();
+        proc.add(10,"a.exe",1,0,&["/games/a.exe","--flag"],&[]);
+        proc.add(11,"bin",1,0,&["bin"],&[]);
+        std::os::unix::fs::symlink("/opt/game/bin",proc.0.path().join("11").join("exe")).unwrap();
+
+        assert_eq!(proc.scanner().pids_for_exe(Path::new("/games/a.exe")),[10]);
+        assert_eq!(proc.scanner().pids_for_exe(Path::new("/opt/game/bin")),[11]);
+        assert!(proc.scanner().pids_for_exe(Path::new("/usr/bin/bash")).is_empty());
+    }
+}
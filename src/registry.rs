@@ -0,0 +1,156 @@
+use napi_derive::napi;
+#[allow(unused_imports)]
+use crate::error::{self,ErrorCode};
+
+// Read access to `HKCU\SOFTWARE\Valve\Steam`. On Linux, Steam mirrors this key into `registry.vdf`
+// under `Registry/HKCU/Software/Valve/Steam`. Value paths are relative to that key and slash-separated,
+// e.g. `RunningAppID` or `Apps/440/Name`
+pub struct SteamRegistry {
+    #[cfg(target_os="windows")]
+    hkcu: winreg::RegKey,
+    #[cfg(not(target_os="windows"))]
+    root: crate::vdf::VdfValue
+}
+
+#[cfg(not(target_os="windows"))]
+pub const REGISTRY_VDF_KEY: &str = "Registry/HKCU/Software/Valve/Steam";
+
+impl SteamRegistry {
+    #[cfg(target_os="windows")]
+    pub fn open() -> error::Result<Self> {
+        use crate::win32::{RegKey,HKEY_CURRENT_USER};
+
+        Ok(SteamRegistry {
+            hkcu: RegKey::predef(HKEY_CURRENT_USER)
+        })
+    }
+
+    #[cfg(target_os="windows")]
+    pub fn get_value(&self,path: &str) -> Option<String> {
+        use crate::win32::STEAMREGPATH;
+
+        let (subkey,name) = match path.rsplit_once('/') {
+            Some((subkey,name)) => (format!("{}\\{}",STEAMREGPATH,subkey.replace('/',"\\")),name),
+            None => (STEAMREGPATH.to_string(),path)
+        };
+
+        let regkey = self.hkcu.open_subkey(&subkey).ok()?;
+
+        regkey
+            .get_value::<String,_>(name)
+            .ok()
+            .or_else(|| regkey
+                .get_value::<u32,_>(name)
+                .ok()
+                .map(|val| val.to_string())
+            )
+    }
+
+    #[cfg(target_os="linux")]
+    pub fn open() -> error::Result<Self> {
+        use crate::linux::home_dir;
+
+        let home = home_dir().ok_or_else(|| error::err(ErrorCode::FileNotFound,"Failed to locate \"homedir\""))?;
+
+        // Each packaging format keeps its own `~/.steam`, so follow the active install
+        let steam_dir = match crate::steam::get_steam_installs().into_iter().find(|install| install.active) {
+            Some(install) if install.kind == "flatpak" => home.join(".var/app/com.valvesoftware.Steam/.steam"),
+            Some(install) if install.kind == "snap" => home.join("snap/steam/common/.steam"),
+            _ => home.join(".steam")
+        };
+
+        SteamRegistry::from_vdf(&steam_dir.join("registry.vdf"))
+    }
+
+    #[cfg(not(target_os="windows"))]
+    pub fn from_vdf(path: &std::path::Path) -> error::Result<Self> {
+        let parsed = crate::vdf::load(path)?;
+
+        let root = parsed
+            .get_path(REGISTRY_VDF_KEY)
+            .cloned()
+            .ok_or_else(|| error::err(ErrorCode::KeyNotFound,format!("\"{}\" not found in \"{}\"",REGISTRY_VDF_KEY,path.display())))?;
+
+        Ok(SteamRegistry { root })
+    }
+
+    #[cfg(not(target_os="windows"))]
+    pub fn get_value(&self,path: &str) -> Option<String> {
+        use crate::vdf::VdfValue;
+
+        self.root
+            .get_path(path)
+            .and_then(|value| match value {
+                // Returned as written, matching `REG_SZ` values on Windows
+                VdfValue::Str(val) => Some(val.clone()),
+                VdfValue::Int(val) => Some(val.to_string()),
+                VdfValue::UInt64(val) => Some(val.to_string()),
+                VdfValue::Int64(val) => Some(val.to_string()),
+                VdfValue::Float(val) => Some(val.to_string()),
+                VdfValue::Obj(_) => None
+            })
+    }
+
+    #[cfg(not(any(target_os="windows",target_os="linux")))]
+    pub fn open() -> error::Result<Self> {
+        Err(error::err(ErrorCode::SteamNotFound,"Unsupported OS"))
+    }
+
+    pub fn get_u32(&self,path: &str) -> Option<u32> {
+        self.get_value(path).and_then(|val| val.trim().parse().ok())
+    }
+
+    pub fn running_appid(&self) -> Option<u32> {
+        self.get_u32("RunningAppID").filter(|&appid| appid != 0)
+    }
+
+    pub fn app_name(&self,appid: u32) -> Option<String> {
+        self.get_value(&format!("Apps/{}/Name",appid)).filter(|name| !name.is_empty())
+    }
+
+    pub fn steam_path(&self) -> Option<String> {
+        self.get_value("SteamPath").filter(|path| !path.is_empty())
+    }
+
+    // Account id of the signed-in user, or `None` when Steam is not running
+    pub fn active_user(&self) -> Option<u32> {
+        self.get_u32("ActiveProcess/ActiveUser").filter(|&accountid| accountid != 0)
+    }
+
+    pub fn active_pid(&self) -> Option<u32> {
+        self.get_u32("ActiveProcess/pid").filter(|&pid| pid != 0)
+    }
+}
+
+#[napi]
+pub fn get_steam_registry_value(path: String) -> error::Result<Option<String>> {
+    Ok(SteamRegistry::open()?.get_value(&path))
+}
+
+#[cfg(all(test,not(target_os="windows")))]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn reads_registry_vdf() {
+        let registry = SteamRegistry::from_vdf(&fixture("registry.vdf")).unwrap();
+
+        assert_eq!(registry.running_appid(),Some(440));
+        assert_eq!(registry.app_name(440).as_deref(),Some("Team Fortress 2"));
+        assert_eq!(registry.app_name(2002),None);
+        assert_eq!(registry.app_name(730),None);
+        assert_eq!(registry.active_user(),Some(12345));
+        assert_eq!(registry.active_pid(),Some(4242));
+        assert_eq!(registry.steam_path().as_deref(),Some("/home/deck/.local/share/Steam"));
+    }
+
+    #[test]
+    fn keeps_numeric_looking_strings() {
+        let registry = SteamRegistry::from_vdf(&fixture("registry.vdf")).unwrap();
+
+        assert_eq!(registry.app_name(2001).as_deref(),Some("007"));
+        assert_eq!(registry.get_value("Rate").as_deref(),Some("+5"));
+        assert_eq!(registry.get_value("Apps/440"),None);
+    }
+}
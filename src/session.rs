@@ -0,0 +1,168 @@
+use napi_derive::napi;
+use std::{collections::HashMap,env,fs,path::{Path,PathBuf}};
+
+// DMI product names of the LCD and OLED Steam Deck
+const STEAM_DECK_PRODUCTS: [&str;2] = ["Jupiter","Galileo"];
+
+#[napi(object)]
+#[derive(Clone,Debug,PartialEq)]
+pub struct SessionInfo {
+    /// Running inside gamescope, either Gaming Mode on a Deck or a nested `gamescope` session
+    pub gamescope: bool,
+    pub steam_deck: bool,
+    /// DMI product name, e.g. `Jupiter` or `Galileo` on a Steam Deck
+    pub product_name: String,
+    /// `x11`, `xwayland`, `wayland`, `windows` or `unknown`
+    pub display_server: String,
+    /// `XDG_CURRENT_DESKTOP`
+    pub desktop: String
+}
+
+// Reads the environment and sysfs (or stand-ins for tests)
+pub struct SessionProbe {
+    env: HashMap<String,String>,
+    sys_root: PathBuf
+}
+
+impl Default for SessionProbe {
+    fn default() -> Self {
+        // `env::vars` panics on a variable that isn't valid UTF-8
+        let env = env::vars_os()
+            .map(|(key,val)| (key.to_string_lossy().into_owned(),val.to_string_lossy().into_owned()))
+            .collect();
+
+        SessionProbe::new(env,"/sys")
+    }
+}
+
+impl SessionProbe {
+    pub fn new<P: AsRef<Path>>(env: HashMap<String,String>,sys_root: P) -> Self {
+        SessionProbe {
+            env,
+            sys_root: sys_root.as_ref().to_path_buf()
+        }
+    }
+
+    fn var(&self,key: &str) -> Option<&str> {
+        self.env
+            .get(key)
+            .map(|val| val.trim())
+            .filter(|val| !val.is_empty())
+    }
+
+    pub fn product_name(&self) -> String {
+        fs::read_to_string(self.sys_root.join("class").join("dmi").join("id").join("product_name"))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn is_gamescope(&self) -> bool {
+        self.var("GAMESCOPE_WAYLAND_DISPLAY").is_some()
+            || self.var("XDG_CURRENT_DESKTOP").is_some_and(|desktop| desktop.eq_ignore_ascii_case("gamescope"))
+            || self.var("SteamDeck") == Some("1")
+    }
+
+    // X11 clients under a Wayland compositor (including gamescope) go through XWayland
+    pub fn display_server(&self) -> &'static str {
+        let wayland = self.var("WAYLAND_DISPLAY").is_some()
+            || self.var("GAMESCOPE_WAYLAND_DISPLAY").is_some()
+            || self.var("XDG_SESSION_TYPE").is_some_and(|session| session.eq_ignore_ascii_case("wayland"));
+
+        match (wayland,self.var("DISPLAY").is_some()) {
+            (true,true) => "xwayland",
+            (true,false) => "wayland",
+            (false,true) => "x11",
+            (false,false) => "unknown"
+        }
+    }
+
+    pub fn session_info(&self) -> SessionInfo {
+        let product_name = self.product_name();
+
+        SessionInfo {
+            gamescope: self.is_gamescope(),
+            steam_deck: STEAM_DECK_PRODUCTS.contains(&product_name.as_str()),
+            product_name,
+            display_server: self.display_server().to_string(),
+            desktop: self.var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_string()
+        }
+    }
+}
+
+#[napi]
+pub fn get_session_info() -> SessionInfo {
+    #[allow(unused_mut)]
+    let mut info = SessionProbe::default().session_info();
+
+    #[cfg(target_os="windows")] {
+        info.display_server = "windows".to_string();
+    }
+
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn probe(vars: &[(&str,&str)],product_name: Option<&str>) -> (SessionProbe,TempDir) {
+        let sys = tempfile::tempdir().unwrap();
+
+        if let Some(name) = product_name {
+            let dmi = sys.path().join("class").join("dmi").join("id");
+            fs::create_dir_all(&dmi).unwrap();
+            fs::write(dmi.join("product_name"),format!("{}\n",name)).unwrap();
+        }
+
+        let env = vars.iter().map(|(k,v)| (k.to_string(),v.to_string())).collect();
+        (SessionProbe::new(env,sys.path()),sys)
+    }
+
+    #[test]
+    fn steam_deck_gaming_mode() {
+        let (probe,_sys) = probe(&[("GAMESCOPE_WAYLAND_DISPLAY","gamescope-0"),("DISPLAY",":0"),("XDG_CURRENT_DESKTOP","gamescope")],Some("Jupiter"));
+
+        assert_eq!(probe.session_info(),SessionInfo {
+            gamescope: true,
+            steam_deck: true,
+            product_name: "Jupiter".to_string(),
+            display_server: "xwayland".to_string(),
+            desktop: "gamescope".to_string()
+        });
+    }
+
+    #[test]
+    fn oled_deck_desktop_mode() {
+        let (probe,_sys) = probe(&[("DISPLAY",":0"),("XDG_SESSION_TYPE","x11"),("XDG_CURRENT_DESKTOP","KDE")],Some("Galileo"));
+        let info = probe.session_info();
+
+        assert!(!info.gamescope);
+        assert!(info.steam_deck);
+        assert_eq!(info.display_server,"x11");
+        assert_eq!(info.desktop,"KDE");
+    }
+
+    #[test]
+    fn nested_gamescope_on_a_desktop() {
+        let (probe,_sys) = probe(&[("SteamDeck","1"),("WAYLAND_DISPLAY","wayland-0")],Some("Desktop PC"));
+        let info = probe.session_info();
+
+        assert!(info.gamescope);
+        assert!(!info.steam_deck);
+        assert_eq!(info.display_server,"wayland");
+    }
+
+    #[test]
+    fn empty_environment() {
+        let (probe,_sys) = probe(&[("DISPLAY","  "),("SteamDeck","0")],None);
+
+        assert_eq!(probe.session_info(),SessionInfo {
+            gamescope: false,
+            steam_deck: false,
+            product_name: String::new(),
+            display_server: "unknown".to_string(),
+            desktop: String::new()
+        });
+    }
+}
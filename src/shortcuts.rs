@@ -0,0 +1,151 @@
+use napi_derive::napi;
+use std::{fs,path::{Path,PathBuf}};
+use crate::{error,library,vdf::{self,VdfValue}};
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct Shortcut {
+    /// 32-bit shortcut id, as reported in `AppId=` and used for `userdata/<accountid>/config/grid` artwork
+    pub appid: u32,
+    /// 64-bit game id, as used in `steam://rungameid/<gameid>`
+    pub gameid: String,
+    pub app_name: String,
+    pub exe: String,
+    pub start_dir: String,
+    pub icon: String,
+    pub launch_options: String,
+    pub tags: Vec<String>,
+    pub is_hidden: bool,
+    /// Unix timestamp
+    pub last_play_time: u32
+}
+
+pub fn is_shortcut_appid(appid: u32) -> bool {
+    appid & 0x80000000 != 0
+}
+
+// Steam derives a shortcut's id from the CRC32 of its quoted exe path and name, with the top bit set
+pub fn shortcut_appid(exe: &str,app_name: &str) -> u32 {
+    crc32fast::hash(format!("{}{}",exe,app_name).as_bytes()) | 0x80000000
+}
+
+pub fn shortcut_gameid(appid: u32) -> u64 {
+    ((appid as u64) << 32) | 0x02000000
+}
+
+fn parse_shortcut(value: &VdfValue) -> Shortcut {
+    let get = |key: &str| value.get_str(key).unwrap_or_default().to_string();
+
+    let app_name = get("AppName");
+    let exe = get("Exe");
+
+    // Older clients did not store `appid`, so derive it the way Steam does
+    let appid = value
+        .get_i64("appid")
+        .map(|appid| appid as u32)
+        .filter(|&appid| appid != 0)
+        .unwrap_or_else(|| shortcut_appid(&exe,&app_name));
+
+    Shortcut {
+        appid,
+        gameid: shortcut_gameid(appid).to_string(),
+        app_name,
+        exe,
+        start_dir: get("StartDir"),
+        icon: get("icon"),
+        launch_options: get("LaunchOptions"),
+        tags: value
+            .get("tags")
+            .map(|tags| tags
+                .entries()
+                .iter()
+                .filter_map(|(_,tag)| tag.as_str().map(str::to_string))
+                .collect()
+            )
+            .unwrap_or_default(),
+        is_hidden: value.get_i64("IsHidden").is_some_and(|hidden| hidden != 0),
+        last_play_time: value.get_i64("LastPlayTime").unwrap_or(0) as u32
+    }
+}
+
+pub fn shortcuts_path(steam_path: &Path,accountid: u32) -> PathBuf {
+    steam_path
+        .join("userdata")
+        .join(accountid.to_string())
+        .join("config")
+        .join("shortcuts.vdf")
+}
+
+pub fn shortcuts(steam_path: &Path,accountid: u32) -> error::Result<Vec<Shortcut>> {
+    let kv = vdf::load_binary(&shortcuts_path(steam_path,accountid))?;
+
+    Ok(kv
+        .get("shortcuts")
+        .map(|shortcuts| shortcuts
+            .entries()
+            .iter()
+            .map(|(_,shortcut)| parse_shortcut(shortcut))
+            .collect()
+        )
+        .unwrap_or_default()
+    )
+}
+
+// Searches the shortcuts of every account in `userdata`
+pub fn find_shortcut(steam_path: &Path,appid: u32) -> Option<Shortcut> {
+    fs::read_dir(steam_path.join("userdata"))
+        .ok()?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|accountid| shortcuts(steam_path,accountid).ok())
+        .flatten()
+        .find(|shortcut| shortcut.appid == appid)
+}
+
+#[napi]
+pub fn get_shortcuts(accountid: u32) -> error::Result<Vec<Shortcut>> {
+    shortcuts(&library::steam_root()?,accountid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn derives_appid_from_exe_and_name() {
+        // Expected values from zlib's CRC32 of the quoted exe path followed by the name
+        assert_eq!(shortcut_appid("\"/home/deck/Games/Celeste/Celeste\"","Celeste"),3554462823);
+        assert_eq!(shortcut_appid("\"/usr/bin/retroarch\"","RetroArch"),3985023816);
+        assert!(is_shortcut_appid(3554462823));
+        assert!(!is_shortcut_appid(440));
+        assert_eq!(shortcut_gameid(3554462823),15266301579666391040);
+    }
+
+    #[test]
+    fn reads_shortcuts_vdf() {
+        let shortcuts = shortcuts(&fixture("steam"),12345).unwrap();
+        assert_eq!(shortcuts.len(),2);
+
+        // Stored as a negative `Int`
+        let celeste = &shortcuts[0];
+        assert_eq!(celeste.appid,3554462823);
+        assert_eq!(celeste.gameid,"15266301579666391040");
+        assert_eq!(celeste.app_name,"Celeste");
+        assert_eq!(celeste.exe,"\"/home/deck/Games/Celeste/Celeste\"");
+        assert_eq!(celeste.launch_options,"--fullscreen");
+        assert_eq!(celeste.tags,["favorite","Platformer"]);
+        assert!(!celeste.is_hidden);
+        assert_eq!(celeste.last_play_time,1700000000);
+
+        // No stored `appid`, so it is derived
+        let retroarch = &shortcuts[1];
+        assert_eq!(retroarch.appid,3985023816);
+        assert_eq!(retroarch.gameid,"17115546963534675968");
+        assert!(retroarch.is_hidden);
+        assert!(retroarch.tags.is_empty());
+
+        assert_eq!(find_shortcut(&fixture("steam"),3985023816).map(|shortcut| shortcut.app_name).as_deref(),Some("RetroArch"));
+        assert!(find_shortcut(&fixture("steam"),0x80000001).is_none());
+    }
+}
@@ -0,0 +1,367 @@
+use napi_derive::napi;
+use std::{collections::HashMap,path::{Path,PathBuf}};
+use crate::{error::{self,ErrorCode},library,vdf::{self,VdfValue}};
+
+// `ESteamUserStatType`
+const STAT_INT: i64 = 1;
+const STAT_FLOAT: i64 = 2;
+const STAT_AVGRATE: i64 = 3;
+const STAT_ACHIEVEMENTS: i64 = 4;
+const STAT_GROUPACHIEVEMENTS: i64 = 5;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct AchievementSchema {
+    pub apiname: String,
+    /// Display names keyed by Steam language name (`english`, `german`...)
+    pub display_name: HashMap<String,String>,
+    pub description: HashMap<String,String>,
+    /// Hash of the icon under the CDN `<appid>/` path (`<hash>.jpg`)
+    pub icon: String,
+    pub icon_gray: String,
+    pub hidden: bool,
+    /// Id of the stat whose bitfield stores this achievement
+    pub stat_id: u32,
+    pub bit: u32
+}
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct StatSchema {
+    pub id: u32,
+    pub name: String,
+    /// `int`, `float` or `avgrate`
+    #[napi(js_name = "type")]
+    pub stat_type: String,
+    pub display_name: String
+}
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct GameStatsSchema {
+    pub appid: u32,
+    pub gamename: String,
+    pub version: u32,
+    pub stats: Vec<StatSchema>,
+    pub achievements: Vec<AchievementSchema>
+}
+
+pub fn stats_dir(steam_path: &Path) -> PathBuf {
+    steam_path
+        .join("appcache")
+        .join("stats")
+}
+
+// Localized strings are stored per language, alongside a `token` key naming the localization token
+fn localized(value: Option<&VdfValue>) -> HashMap<String,String> {
+    value
+        .map(|value| match value {
+            VdfValue::Str(val) => HashMap::from([("english".to_string(),val.clone())]),
+            _ => value
+                .entries()
+                .iter()
+                .filter(|(lang,_)| !lang.eq_ignore_ascii_case("token"))
+                .filter_map(|(lang,val)| val.as_str().map(|val| (lang.clone(),val.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_achievement(stat_id: u32,key: &str,value: &VdfValue) -> AchievementSchema {
+    let display = value.get("display");
+    let get = |key: &str| display
+        .and_then(|display| display.get_str(key))
+        .unwrap_or_default()
+        .to_string();
+
+    AchievementSchema {
+        apiname: value.get_str("name").unwrap_or_default().to_string(),
+        display_name: localized(display.and_then(|display| display.get("name"))),
+        description: localized(display.and_then(|display| display.get("desc"))),
+        icon: get("icon"),
+        icon_gray: get("icon_gray"),
+        hidden: display
+            .and_then(|display| display.get_i64("hidden"))
+            .is_some_and(|hidden| hidden != 0),
+        stat_id,
+        bit: value
+            .get_i64("bit")
+            .or_else(|| key.parse().ok())
+            .unwrap_or(0) as u32
+    }
+}
+
+pub fn parse_schema(appid: u32,kv: &VdfValue) -> GameStatsSchema {
+    // The root key is the appid
+    let root = kv
+        .entries()
+        .first()
+        .map(|(_,root)| root)
+        .unwrap_or(kv);
+
+    let mut stats = Vec::new();
+    let mut achievements = Vec::new();
+
+    for (key,stat) in root.get("stats").map(VdfValue::entries).unwrap_or_default() {
+        let id = stat
+            .get_i64("id")
+            .or_else(|| key.parse().ok())
+            .unwrap_or(0) as u32;
+
+        match stat.get_i64("type").unwrap_or(0) {
+            STAT_ACHIEVEMENTS | STAT_GROUPACHIEVEMENTS => {
+                for (bit,achievement) in stat.get("bits").map(VdfValue::entries).unwrap_or_default() {
+                    achievements.push(parse_achievement(id,bit,achievement));
+                }
+            },
+            stat_type @ (STAT_INT | STAT_FLOAT | STAT_AVGRATE) => stats.push(StatSchema {
+                id,
+                name: stat.get_str("name").unwrap_or_default().to_string(),
+                stat_type: match stat_type {
+                    STAT_INT => "int",
+                    STAT_FLOAT => "float",
+                    _ => "avgrate"
+                }.to_string(),
+                display_name: stat
+                    .get_path("display/name")
+                    .and_then(VdfValue::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            }),
+            _ => {}
+        }
+    }
+
+    GameStatsSchema {
+        appid,
+        gamename: root.get_str("gamename").unwrap_or_default().to_string(),
+        version: root.get_i64("version").unwrap_or(0) as u32,
+        stats,
+        achievements
+    }
+}
+
+pub fn achievement_schema(steam_path: &Path,appid: u32) -> error::Result<GameStatsSchema> {
+    let path = stats_dir(steam_path).join(format!("UserGameStatsSchema_{}.bin",appid));
+    let kv = vdf::load_binary(&path)?;
+
+    match kv.entries().is_empty() {
+        true => Err(error::err(ErrorCode::KeyNotFound,format!("\"{}\" contains no schema",path.display()))),
+        false => Ok(parse_schema(appid,&kv))
+    }
+}
+
+#[napi]
+pub fn get_achievement_schema(appid: u32) -> error::Result<GameStatsSchema> {
+    achievement_schema(&library::steam_root()?,appid)
+}
+
+#[napi(object)]
+#[derive(Clone,Debug,PartialEq)]
+pub struct AchievementState {
+    pub apiname: String,
+    pub unlocked: bool,
+    /// Unix timestamp, or 0 if locked
+    pub unlocktime: u32
+}
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct StatValue {
+    pub id: u32,
+    pub name: String,
+    pub value: f64
+}
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct UserGameStats {
+    pub appid: u32,
+    pub accountid: u32,
+    pub achievements: Vec<AchievementState>,
+    pub stats: Vec<StatValue>
+}
+
+// Undecoded contents of `UserGameStats_<accountid>_<appid>.bin`, keyed by stat id
+#[derive(Clone,Debug,Default)]
+pub struct RawUserStats {
+    pub data: HashMap<u32,i32>,
+    /// Unlock times keyed by stat id, then bit
+    pub times: HashMap<u32,HashMap<u32,u32>>
+}
+
+pub fn parse_user_stats(kv: &VdfValue) -> RawUserStats {
+    // The root key is `cache`
+    let root = kv
+        .entries()
+        .first()
+        .map(|(_,root)| root)
+        .unwrap_or(kv);
+
+    let mut raw = RawUserStats::default();
+
+    for (key,stat) in root.entries() {
+        let id = match key.parse::<u32>() {
+            Ok(id) => id,
+            Err(_) => continue // `crc`, `PendingChanges`...
+        };
+
+        if let Some(data) = stat.get_i64("data") {
+            raw.data.insert(id,data as i32);
+        }
+
+        if let Some(times) = stat.get("AchievementTimes") {
+            raw.times.insert(id,times
+                .entries()
+                .iter()
+                .filter_map(|(bit,time)| Some((bit.parse().ok()?,time.as_i64()? as u32)))
+                .collect()
+            );
+        }
+    }
+
+    raw
+}
+
+pub fn achievement_states(schema: &GameStatsSchema,raw: &RawUserStats) -> Vec<AchievementState> {
+    schema.achievements
+        .iter()
+        .map(|achievement| {
+            let bits = raw.data.get(&achievement.stat_id).copied().unwrap_or(0) as u32;
+            let unlocked = achievement.bit < 32 && bits & (1 << achievement.bit) != 0;
+
+            AchievementState {
+                apiname: achievement.apiname.clone(),
+                unlocked,
+                unlocktime: match unlocked {
+                    true => raw.times
+                        .get(&achievement.stat_id)
+                        .and_then(|times| times.get(&achievement.bit))
+                        .copied()
+                        .unwrap_or(0),
+                    false => 0
+                }
+            }
+        })
+        .collect()
+}
+
+// Float stats store the bit pattern of an `f32` in `data`
+pub fn stat_values(schema: &GameStatsSchema,raw: &RawUserStats) -> Vec<StatValue> {
+    schema.stats
+        .iter()
+        .map(|stat| {
+            let data = raw.data.get(&stat.id).copied().unwrap_or(0);
+
+            StatValue {
+                id: stat.id,
+                name: stat.name.clone(),
+                value: match stat.stat_type.as_str() {
+                    "int" => data as f64,
+                    _ => f32::from_bits(data as u32) as f64
+                }
+            }
+        })
+        .collect()
+}
+
+pub fn user_stats_path(steam_path: &Path,accountid: u32,appid: u32) -> PathBuf {
+    stats_dir(steam_path).join(format!("UserGameStats_{}_{}.bin",accountid,appid))
+}
+
+pub fn user_game_stats(steam_path: &Path,appid: u32,accountid: u32) -> error::Result<UserGameStats> {
+    let schema = achievement_schema(steam_path,appid)?;
+    let raw = parse_user_stats(&vdf::load_binary(&user_stats_path(steam_path,accountid,appid))?);
+
+    Ok(UserGameStats {
+        appid,
+        accountid,
+        achievements: achievement_states(&schema,&raw),
+        stats: stat_values(&schema,&raw)
+    })
+}
+
+#[napi]
+pub fn get_achievement_states(appid: u32,accountid: u32) -> error::Result<UserGameStats> {
+    user_game_stats(&library::steam_root()?,appid,accountid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    #[test]
+    fn parses_schema() {
+        let schema = achievement_schema(&fixture("steam"),440).unwrap();
+
+        assert_eq!(schema.gamename,"Team Fortress 2");
+        assert_eq!(schema.version,12);
+        assert_eq!(schema.achievements.len(),33);
+
+        let first = &schema.achievements[0];
+        assert_eq!(first.apiname,"ACH_00");
+        assert_eq!(first.display_name.get("english").map(String::as_str),Some("Achievement 0"));
+        assert_eq!(first.display_name.get("german").map(String::as_str),Some("Achievement 0 DE"));
+        assert!(!first.display_name.contains_key("token"));
+        assert_eq!(first.description.get("english").map(String::as_str),Some("Achievement 0 desc"));
+        assert_eq!((first.icon.as_str(),first.icon_gray.as_str()),("ach_00.jpg","ach_00_gray.jpg"));
+        assert!(!first.hidden);
+
+        let last_bit = &schema.achievements[31];
+        assert_eq!((last_bit.stat_id,last_bit.bit),(1,31));
+        assert!(last_bit.hidden);
+
+        // The 33rd achievement starts the next bitfield stat
+        let overflow = &schema.achievements[32];
+        assert_eq!(overflow.apiname,"ACH_32");
+        assert_eq!((overflow.stat_id,overflow.bit),(2,0));
+
+        let stats: Vec<(u32,&str,&str,&str)> = schema.stats
+            .iter()
+            .map(|stat| (stat.id,stat.name.as_str(),stat.stat_type.as_str(),stat.display_name.as_str()))
+            .collect();
+
+        assert_eq!(stats,[(3,"kills","int","Kills"),(4,"distance","float","Distance")]);
+    }
+
+    #[test]
+    fn decodes_user_stats() {
+        let stats = user_game_stats(&fixture("steam"),440,12345).unwrap();
+        let state = |apiname: &str| stats.achievements
+            .iter()
+            .find(|state| state.apiname == apiname)
+            .map(|state| (state.unlocked,state.unlocktime))
+            .unwrap();
+
+        assert_eq!(stats.achievements.len(),33);
+        assert_eq!(state("ACH_00"),(true,1600000000));
+        assert_eq!(state("ACH_01"),(false,0));
+        assert_eq!(state("ACH_05"),(true,1600000500));
+        // Bit 31 makes the stored `i32` negative
+        assert_eq!(state("ACH_31"),(true,1600003100));
+        assert_eq!(state("ACH_32"),(true,1700000000));
+
+        let values: Vec<(&str,f64)> = stats.stats
+            .iter()
+            .map(|stat| (stat.name.as_str(),stat.value))
+            .collect();
+
+        assert_eq!(values,[("kills",42.0),("distance",12.5)]);
+    }
+
+    #[test]
+    fn skips_non_stat_keys() {
+        let raw = parse_user_stats(&vdf::load_binary(&user_stats_path(&fixture("steam"),12345,440)).unwrap());
+        let mut ids: Vec<u32> = raw.data.keys().copied().collect();
+        ids.sort_unstable();
+
+        assert_eq!(ids,[1,2,3,4]);
+        assert_eq!(raw.times[&1].len(),3);
+    }
+
+    #[test]
+    fn missing_schema() {
+        assert_eq!(achievement_schema(&fixture("steam"),730).err().unwrap().status,ErrorCode::FileNotFound);
+    }
+}
@@ -0,0 +1,215 @@
+use napi_derive::napi;
+use extern_log::error;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct SteamInstall {
+    /// Canonical (symlink-resolved) path to the Steam root directory
+    pub path: String,
+    /// Candidate location the install was found through (e.g. `~/.steam/steam`)
+    pub source: String,
+    /// `native`, `flatpak`, `snap` or `registry`
+    pub kind: String,
+    pub active: bool
+}
+
+#[cfg(target_os="linux")]
+mod linux {
+    use super::SteamInstall;
+    use extern_log::info;
+    use std::{fs,path::{Path,PathBuf}};
+
+    /// Candidate Steam roots relative to `$HOME`, in order of preference
+    const CANDIDATES: [(&str,&str);5] = [
+        (".steam/steam","native"),
+        (".steam/root","native"),
+        (".local/share/Steam","native"),
+        (".var/app/com.valvesoftware.Steam/.local/share/Steam","flatpak"),
+        ("snap/steam/common/.local/share/Steam","snap")
+    ];
+
+    /// `~/.steam/steam.pid` equivalents for each packaging format
+    const PIDFILES: [&str;3] = [
+        ".steam/steam.pid",
+        ".var/app/com.valvesoftware.Steam/.steam/steam.pid",
+        "snap/steam/common/.steam/steam.pid"
+    ];
+
+    fn is_steam_root(path: &Path) -> bool {
+        path.join("steamapps").is_dir() && path.join("steam.sh").is_file()
+    }
+
+    /// Resolves the install directory of a running Steam client via its pidfile
+    fn running_root(home: &Path) -> Option<PathBuf> {
+        PIDFILES
+            .iter()
+            .filter_map(|pidfile| fs::read_to_string(home.join(pidfile)).ok())
+            .filter_map(|pid| pid.trim().parse::<u32>().ok())
+            .filter_map(|pid| fs::read_link(format!("/proc/{}/exe",pid)).ok())
+            .next()
+    }
+
+    pub fn find_steam_installs(home: &Path) -> Vec<SteamInstall> {
+        let mut installs: Vec<SteamInstall> = Vec::new();
+
+        for (candidate,kind) in CANDIDATES {
+            let path = home.join(candidate);
+
+            let resolved = match fs::canonicalize(&path) {
+                Ok(resolved) => resolved,
+                Err(_) => continue
+            };
+
+            if !is_steam_root(&resolved) {
+                info!("\"{}\" is not a valid Steam root",resolved.display());
+                continue
+            }
+
+            let resolved = resolved.to_string_lossy().into_owned();
+
+            if installs.iter().any(|install| install.path == resolved) {
+                continue
+            }
+
+            installs.push(SteamInstall {
+                path: resolved,
+                source: format!("~/{}",candidate),
+                kind: kind.to_string(),
+                active: false
+            });
+        }
+
+        if installs.is_empty() {
+            // Polled through `getAppInfo`, so a missing install is not worth an error every call
+            info!("No Steam installation found in \"{}\"",home.display());
+            return installs
+        }
+
+        // Prefer the install a running client was launched from, then the `~/.steam/root` link maintained by `steam.sh`
+        let active = running_root(home)
+            .and_then(|exe| installs.iter().position(|install| exe.starts_with(&install.path)))
+            .or_else(|| fs::canonicalize(home.join(".steam/root"))
+                .ok()
+                .and_then(|root| installs.iter().position(|install| Path::new(&install.path) == root))
+            )
+            .unwrap_or(0);
+
+        installs[active].active = true;
+        installs
+    }
+}
+
+#[cfg(target_os="linux")]
+pub use linux::find_steam_installs;
+
+#[allow(unreachable_code)]
+#[napi]
+pub fn get_steam_installs() -> Vec<SteamInstall> {
+    #[cfg(target_os="windows")] {
+        use crate::{registry::SteamRegistry,win32::STEAMREGPATH};
+
+        match SteamRegistry::open().map(|registry| registry.steam_path()) {
+            Ok(Some(path)) => return vec![SteamInstall {
+                path,
+                source: format!("HKCU\\{}\\SteamPath",STEAMREGPATH),
+                kind: "registry".to_string(),
+                active: true
+            }],
+            Ok(None) => error!("Failed to read \"SteamPath\" from \"{}\"",STEAMREGPATH),
+            Err(err) => error!("Failed to open Steam registry: {}",err)
+        }
+    }
+
+    #[cfg(target_os="linux")] {
+        use crate::linux::home_dir;
+
+        return match home_dir() {
+            Some(home) => find_steam_installs(&home),
+            None => {
+                error!("Failed to locate \"homedir\"");
+                Vec::new()
+            }
+        }
+    }
+
+    #[cfg(not(any(target_os="windows",target_os="linux")))] {
+        error!("Unsupported OS");
+    }
+
+    Vec::new()
+}
+
+/// Path of the active Steam install, or an empty string if none was found
+pub fn active_steam_path() -> String {
+    get_steam_installs()
+        .into_iter()
+        .find(|install| install.active)
+        .map(|install| install.path)
+        .unwrap_or_default()
+}
+
+#[cfg(all(test,target_os="linux"))]
+mod tests {
+    use super::*;
+    use std::{fs,os::unix::fs::symlink,path::Path};
+
+    fn steam_root(path: &Path) {
+        fs::create_dir_all(path.join("steamapps")).unwrap();
+        fs::write(path.join("steam.sh"),"").unwrap();
+    }
+
+    #[test]
+    fn dedups_symlinked_roots() {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path().join(".local/share/Steam");
+        steam_root(&root);
+        fs::create_dir(home.path().join(".steam")).unwrap();
+        symlink(&root,home.path().join(".steam/steam")).unwrap();
+        symlink(&root,home.path().join(".steam/root")).unwrap();
+
+        let installs = find_steam_installs(home.path());
+
+        assert_eq!(installs.len(),1);
+        assert_eq!(installs[0].path,fs::canonicalize(&root).unwrap().to_string_lossy());
+        assert_eq!(installs[0].source,"~/.steam/steam");
+        assert_eq!(installs[0].kind,"native");
+        assert!(installs[0].active);
+    }
+
+    #[test]
+    fn prefers_the_steam_root_link() {
+        let home = tempfile::tempdir().unwrap();
+        let native = home.path().join(".local/share/Steam");
+        let flatpak = home.path().join(".var/app/com.valvesoftware.Steam/.local/share/Steam");
+        steam_root(&native);
+        steam_root(&flatpak);
+        fs::create_dir(home.path().join(".steam")).unwrap();
+        symlink(&native,home.path().join(".steam/steam")).unwrap();
+        symlink(&flatpak,home.path().join(".steam/root")).unwrap();
+
+        let installs = find_steam_installs(home.path());
+        let found: Vec<(&str,bool)> = installs.iter().map(|install| (install.source.as_str(),install.active)).collect();
+
+        assert_eq!(found,[("~/.steam/steam",false),("~/.steam/root",true)]);
+        assert_eq!(installs[1].path,fs::canonicalize(&flatpak).unwrap().to_string_lossy());
+    }
+
+    #[test]
+    fn defaults_to_the_first_install() {
+        let home = tempfile::tempdir().unwrap();
+        steam_root(&home.path().join("snap/steam/common/.local/share/Steam"));
+        fs::create_dir_all(home.path().join(".local/share/Steam/steamapps")).unwrap();
+
+        let installs = find_steam_installs(home.path());
+
+        assert_eq!(installs.len(),1);
+        assert_eq!(installs[0].kind,"snap");
+        assert!(installs[0].active);
+    }
+
+    #[test]
+    fn finds_nothing_in_an_empty_home() {
+        let home = tempfile::tempdir().unwrap();
+        assert!(find_steam_installs(home.path()).is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use napi_derive::napi;
+use std::path::Path;
+use crate::{error::{self,ErrorCode},library,registry::SteamRegistry,vdf};
+
+// Universe 1 (public), account type 1 (individual), instance 1 (desktop)
+const STEAMID64_BASE: u64 = 76561197960265728;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct SteamUser {
+    /// SteamID64. 64-bit ids are strings throughout, as they exceed `Number.MAX_SAFE_INTEGER`
+    pub steamid64: String,
+    /// 32-bit account id, as used in `userdata/<accountid>` and stats filenames
+    pub accountid: u32,
+    pub account_name: String,
+    pub persona_name: String,
+    pub most_recent: bool,
+    pub wants_offline_mode: bool,
+    /// Unix timestamp of the last login
+    pub timestamp: i64
+}
+
+#[napi(object)]
+#[derive(Clone,Debug,PartialEq)]
+pub struct SteamId {
+    pub steamid64: String,
+    pub accountid: u32,
+    /// `[U:1:<accountid>]`
+    pub steam3: String
+}
+
+pub fn steamid64_to_accountid(steamid64: u64) -> u32 {
+    (steamid64 & 0xFFFFFFFF) as u32
+}
+
+pub fn accountid_to_steamid64(accountid: u32) -> u64 {
+    STEAMID64_BASE + accountid as u64
+}
+
+pub fn accountid_to_steam3(accountid: u32) -> String {
+    format!("[U:1:{}]",accountid)
+}
+
+// Accepts a SteamID64, a 32-bit account id or a `[U:1:n]` SteamID3
+pub fn parse_steamid(id: &str) -> Option<u32> {
+    let id = id.trim();
+
+    if let Some(steam3) = id.strip_prefix("[U:1:").and_then(|id| id.strip_suffix(']')) {
+        return steam3.parse().ok()
+    }
+
+    // Ids past the individual account range belong to other universes or account types, e.g. groups
+    match id.parse::<u64>().ok()? {
+        id if id >= STEAMID64_BASE => u32::try_from(id - STEAMID64_BASE).ok(),
+        id => u32::try_from(id).ok()
+    }
+}
+
+impl SteamId {
+    pub fn from_accountid(accountid: u32) -> Self {
+        SteamId {
+            steamid64: accountid_to_steamid64(accountid).to_string(),
+            accountid,
+            steam3: accountid_to_steam3(accountid)
+        }
+    }
+}
+
+pub fn steam_users(steam_path: &Path) -> error::Result<Vec<SteamUser>> {
+    let parsed = vdf::load(&steam_path.join("config").join("loginusers.vdf"))?;
+    let users = parsed.get("users").map(|users| users.entries()).unwrap_or_default();
+
+    Ok(users
+        .iter()
+        .filter_map(|(steamid64,user)| {
+            let id = steamid64.parse::<u64>().ok()?;
+            let get = |key: &str| user.get_str(key).unwrap_or_default().to_string();
+            let flag = |key: &str| user.get_i64(key).is_some_and(|val| val != 0);
+
+            Some(SteamUser {
+                steamid64: steamid64.clone(),
+                accountid: steamid64_to_accountid(id),
+                account_name: get("AccountName"),
+                persona_name: get("PersonaName"),
+                most_recent: flag("MostRecent"),
+                wants_offline_mode: flag("WantsOfflineMode"),
+                timestamp: user.get_i64("Timestamp").unwrap_or(0)
+            })
+        })
+        .collect()
+    )
+}
+
+// The user signed in to the running client, then the one flagged `MostRecent`, then the one who logged in last
+pub fn current_user(steam_path: &Path) -> error::Result<SteamUser> {
+    let users = steam_users(steam_path)?;
+    let active = SteamRegistry::open()
+        .ok()
+        .and_then(|registry| registry.active_user());
+
+    users
+        .iter()
+        .find(|user| active.is_some_and(|accountid| user.accountid == accountid))
+        .or_else(|| users.iter().find(|user| user.most_recent))
+        .or_else(|| users.iter().max_by_key(|user| user.timestamp))
+        .cloned()
+        .ok_or_else(|| error::err(ErrorCode::KeyNotFound,"No users found in \"loginusers.vdf\""))
+}
+
+#[napi]
+pub fn get_steam_users() -> error::Result<Vec<SteamUser>> {
+    steam_users(&library::steam_root()?)
+}
+
+#[napi]
+pub fn convert_steam_id(id: String) -> error::Result<SteamId> {
+    parse_steamid(&id)
+        .map(SteamId::from_accountid)
+        .ok_or_else(|| error::err(ErrorCode::InvalidArgument,format!("\"{}\" is not a SteamID64, account id or SteamID3",id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_formats() {
+        assert_eq!(accountid_to_steamid64(22202),76561197960287930);
+        assert_eq!(steamid64_to_accountid(76561197960287930),22202);
+        assert_eq!(accountid_to_steam3(22202),"[U:1:22202]");
+
+        for accountid in [0,1,22202,u32::MAX] {
+            let id = SteamId::from_accountid(accountid);
+
+            assert_eq!(parse_steamid(&id.steamid64),Some(accountid));
+            assert_eq!(parse_steamid(&id.steam3),Some(accountid));
+            assert_eq!(parse_steamid(&accountid.to_string()),Some(accountid));
+        }
+    }
+
+    #[test]
+    fn parses_every_format() {
+        assert_eq!(parse_steamid("76561197960287930"),Some(22202));
+        assert_eq!(parse_steamid(" [U:1:22202] "),Some(22202));
+        assert_eq!(parse_steamid("22202"),Some(22202));
+        assert_eq!(SteamId::from_accountid(22202),SteamId {
+            steamid64: "76561197960287930".to_string(),
+            accountid: 22202,
+            steam3: "[U:1:22202]".to_string()
+        });
+    }
+
+    #[test]
+    fn rejects_invalid_ids() {
+        let invalid = [
+            "",
+            "x",
+            "-1",
+            "22202.5",
+            "[U:1:x]",
+            "[U:1:]",
+            "[U:1:-5]",
+            "[U:1:4294967296]",
+            "[G:1:22202]",
+            "[U:1:22202",
+            "4294967296",
+            "76561202255233024",
+            "103582791429521408",
+            "18446744073709551616"
+        ];
+
+        for id in invalid {
+            assert_eq!(parse_steamid(id),None,"{}",id);
+        }
+
+        assert_eq!(convert_steam_id("[U:1:x]".to_string()).unwrap_err().status,ErrorCode::InvalidArgument);
+    }
+}
@@ -0,0 +1,490 @@
+use napi_derive::napi;
+use std::{fs,path::Path};
+use keyvalues_parser::{Vdf,Value};
+use serde_json::{Map,Value as JsonValue};
+use crate::error::{self,ErrorCode};
+
+// Owned KeyValues tree. Keys keep their original order and may repeat, as they do in Steam's files
+#[derive(Debug,Clone,PartialEq)]
+pub enum VdfValue {
+    Str(String),
+    Int(i32),
+    Float(f32),
+    UInt64(u64),
+    Int64(i64),
+    Obj(Vec<(String,VdfValue)>)
+}
+
+impl VdfValue {
+    fn from_text(value: &Value) -> Self {
+        match value {
+            Value::Str(val) => VdfValue::Str(val.to_string()),
+            Value::Obj(obj) => VdfValue::Obj(obj
+                .iter()
+                .flat_map(|(k,v)| v
+                    .iter()
+                    .map(move |item| (k.to_string(),VdfValue::from_text(item)))
+                )
+                .collect()
+            )
+        }
+    }
+
+    // Steam treats keys case-insensitively, so lookups do too
+    pub fn get(&self,key: &str) -> Option<&VdfValue> {
+        self.entries()
+            .iter()
+            .find(|(k,_)| k.eq_ignore_ascii_case(key))
+            .map(|(_,v)| v)
+    }
+
+    // Follows a slash-separated key path, e.g. `libraryfolders/1/apps`
+    pub fn get_path(&self,path: &str) -> Option<&VdfValue> {
+        path.split('/')
+            .filter(|key| !key.is_empty())
+            .try_fold(self,|value,key| value.get(key))
+    }
+
+    pub fn get_str(&self,key: &str) -> Option<&str> {
+        self.get(key).and_then(VdfValue::as_str)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            VdfValue::Str(val) => Some(val),
+            _ => None
+        }
+    }
+
+    // Text VDF stores every number as a string, binary VDF as a typed value. This accepts both
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            VdfValue::Str(val) => val.trim().parse().ok(),
+            VdfValue::Int(val) => Some(*val as i64),
+            VdfValue::UInt64(val) => Some(*val as i64),
+            VdfValue::Int64(val) => Some(*val),
+            VdfValue::Float(val) => Some(*val as i64),
+            VdfValue::Obj(_) => None
+        }
+    }
+
+    pub fn get_i64(&self,key: &str) -> Option<i64> {
+        self.get(key).and_then(VdfValue::as_i64)
+    }
+
+    pub fn entries(&self) -> &[(String,VdfValue)] {
+        match self {
+            VdfValue::Obj(entries) => entries,
+            _ => &[]
+        }
+    }
+
+    // Repeated keys are collected into an array so no value is dropped. A single value never maps to an array, so one always means a repeat
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            VdfValue::Str(val) => JsonValue::String(val.clone()),
+            VdfValue::Int(val) => JsonValue::from(*val),
+            VdfValue::Float(val) => JsonValue::from(*val),
+            VdfValue::UInt64(val) => JsonValue::String(val.to_string()),
+            VdfValue::Int64(val) => JsonValue::from(*val),
+            VdfValue::Obj(entries) => {
+                let mut map = Map::new();
+
+                for (k,v) in entries {
+                    match map.get_mut(k) {
+                        Some(JsonValue::Array(arr)) => arr.push(v.to_json()),
+                        Some(existing) => *existing = JsonValue::Array(vec![existing.take(),v.to_json()]),
+                        None => {
+                            map.insert(k.clone(),v.to_json());
+                        }
+                    }
+                }
+
+                JsonValue::Object(map)
+            }
+        }
+    }
+}
+
+// Little-endian cursor over Steam's binary files
+pub struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> BinaryReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BinaryReader { data, pos: 0 }
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn seek(&mut self,pos: usize) {
+        self.pos = pos;
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    pub fn read_bytes(&mut self,len: usize) -> error::Result<&'a [u8]> {
+        let bytes = self.pos
+            .checked_add(len)
+            .and_then(|end| self.data.get(self.pos..end))
+            .ok_or_else(|| error::err(ErrorCode::ParseFailed,format!("Unexpected end of data reading {} bytes at offset {}",len,self.pos)))?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> error::Result<[u8;N]> {
+        let mut buf = [0u8;N];
+        buf.copy_from_slice(self.read_bytes(N)?);
+        Ok(buf)
+    }
+
+    pub fn read_u8(&mut self) -> error::Result<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_u32(&mut self) -> error::Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> error::Result<i32> {
+        Ok(i32::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_u64(&mut self) -> error::Result<u64> {
+        Ok(u64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_i64(&mut self) -> error::Result<i64> {
+        Ok(i64::from_le_bytes(self.read_array()?))
+    }
+
+    pub fn read_f32(&mut self) -> error::Result<f32> {
+        Ok(f32::from_le_bytes(self.read_array()?))
+    }
+
+    // Null-terminated UTF-8
+    pub fn read_cstr(&mut self) -> error::Result<String> {
+        let len = self.data[self.pos.min(self.data.len())..]
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| error::err(ErrorCode::ParseFailed,format!("Unterminated string at offset {}",self.pos)))?;
+
+        let val = String::from_utf8_lossy(self.read_bytes(len)?).into_owned();
+        self.pos += 1;
+        Ok(val)
+    }
+
+    // Null-terminated UTF-16LE
+    pub fn read_wstr(&mut self) -> error::Result<String> {
+        let mut units = Vec::new();
+
+        loop {
+            match u16::from_le_bytes(self.read_array()?) {
+                0 => break,
+                unit => units.push(unit)
+            }
+        }
+
+        Ok(String::from_utf16_lossy(&units))
+    }
+}
+
+// Binary KeyValues type bytes
+const BIN_OBJ: u8 = 0x00;
+const BIN_STR: u8 = 0x01;
+const BIN_INT: u8 = 0x02;
+const BIN_FLOAT: u8 = 0x03;
+const BIN_PTR: u8 = 0x04;
+const BIN_WSTR: u8 = 0x05;
+const BIN_COLOR: u8 = 0x06;
+const BIN_UINT64: u8 = 0x07;
+const BIN_END: u8 = 0x08;
+const BIN_INT64: u8 = 0x0A;
+const BIN_END_ALT: u8 = 0x0B;
+
+// Deeper than any file Steam writes, and shallow enough that a corrupt file can't overflow the stack
+const MAX_BINARY_DEPTH: usize = 64;
+
+// Reads binary KeyValues entries up to the end marker of the current object.
+// `strings` is the key table used by appinfo.vdf v29+, where keys are stored as indices instead of inline strings
+pub fn parse_binary(reader: &mut BinaryReader,strings: Option<&[String]>) -> error::Result<VdfValue> {
+    parse_binary_obj(reader,strings,0)
+}
+
+fn parse_binary_obj(reader: &mut BinaryReader,strings: Option<&[String]>,depth: usize) -> error::Result<VdfValue> {
+    if depth > MAX_BINARY_DEPTH {
+        return Err(error::err(ErrorCode::ParseFailed,format!("Binary VDF nested deeper than {} levels at offset {}",MAX_BINARY_DEPTH,reader.pos())))
+    }
+
+    let mut entries = Vec::new();
+
+    loop {
+        let kind = reader.read_u8()?;
+
+        if kind == BIN_END || kind == BIN_END_ALT {
+            break
+        }
+
+        let key = match strings {
+            Some(table) => {
+                let idx = reader.read_u32()? as usize;
+                table
+                    .get(idx)
+                    .cloned()
+                    .ok_or_else(|| error::err(ErrorCode::ParseFailed,format!("Key index {} out of range of string table ({})",idx,table.len())))?
+            },
+            None => reader.read_cstr()?
+        };
+
+        let value = match kind {
+            BIN_OBJ => parse_binary_obj(reader,strings,depth + 1)?,
+            BIN_STR => VdfValue::Str(reader.read_cstr()?),
+            BIN_INT | BIN_PTR | BIN_COLOR => VdfValue::Int(reader.read_i32()?),
+            BIN_FLOAT => VdfValue::Float(reader.read_f32()?),
+            BIN_WSTR => VdfValue::Str(reader.read_wstr()?),
+            BIN_UINT64 => VdfValue::UInt64(reader.read_u64()?),
+            BIN_INT64 => VdfValue::Int64(reader.read_i64()?),
+            other => return Err(error::err(ErrorCode::ParseFailed,format!("Unknown binary VDF type 0x{:02x} for \"{}\" at offset {}",other,key,reader.pos())))
+        };
+
+        entries.push((key,value));
+    }
+
+    Ok(VdfValue::Obj(entries))
+}
+
+pub fn load_binary(path: &Path) -> error::Result<VdfValue> {
+    let data = fs::read(path).map_err(|err| error::io_err(path,err))?;
+
+    parse_binary(&mut BinaryReader::new(&data),None)
+        .map_err(|err| error::err(err.status,format!("Failed to parse \"{}\": {}",path.display(),err.reason)))
+}
+
+// Parses a text VDF file. The result is an object holding the file's root key, so paths start from it
+pub fn load(path: &Path) -> error::Result<VdfValue> {
+    let contents = fs::read_to_string(path).map_err(|err| error::io_err(path,err))?;
+
+    let parsed = Vdf::parse(&contents)
+        .map_err(|err| error::err(ErrorCode::ParseFailed,format!("Failed to parse contents of \"{}\": {}",path.display(),err)))?;
+
+    Ok(VdfValue::Obj(vec![(parsed.key.to_string(),VdfValue::from_text(&parsed.value))]))
+}
+
+enum TextToken {
+    Str(String),
+    Open,
+    Close
+}
+
+// Minimal text KeyValues tokenizer, used to stream large files without building a tree
+struct TextTokens<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> TextTokens<'a> {
+    fn new(contents: &'a str) -> Self {
+        TextTokens {
+            data: contents.as_bytes(),
+            pos: 0
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+            self.pos += 1;
+        }
+    }
+
+    fn next_token(&mut self) -> error::Result<Option<TextToken>> {
+        loop {
+            let Some(&byte) = self.data.get(self.pos) else {
+                return Ok(None)
+            };
+
+            match byte {
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                b'/' if self.data.get(self.pos + 1) == Some(&b'/') => self.skip_line(),
+                // Platform conditionals such as `[$WIN32]` follow a value and are ignored
+                b'[' => {
+                    while self.pos < self.data.len() && self.data[self.pos] != b']' {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                },
+                b'{' => {
+                    self.pos += 1;
+                    return Ok(Some(TextToken::Open))
+                },
+                b'}' => {
+                    self.pos += 1;
+                    return Ok(Some(TextToken::Close))
+                },
+                b'"' => return self.quoted().map(Some),
+                _ => {
+                    let start = self.pos;
+
+                    while self.data.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b,b'{' | b'}' | b'"')) {
+                        self.pos += 1;
+                    }
+
+                    return Ok(Some(TextToken::Str(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())))
+                }
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> error::Result<TextToken> {
+        let start = self.pos;
+        let mut bytes = Vec::new();
+        self.pos += 1;
+
+        loop {
+            let byte = *self.data
+                .get(self.pos)
+                .ok_or_else(|| error::err(ErrorCode::ParseFailed,format!("Unterminated string at offset {}",start)))?;
+
+            self.pos += 1;
+
+            match byte {
+                b'"' => return Ok(TextToken::Str(String::from_utf8_lossy(&bytes).into_owned())),
+                b'\\' => {
+                    let escaped = self.data.get(self.pos).copied().unwrap_or(b'\\');
+                    self.pos += 1;
+
+                    bytes.push(match escaped {
+                        b'n' => b'\n',
+                        b't' => b'\t',
+                        other => other
+                    });
+                },
+                other => bytes.push(other)
+            }
+        }
+    }
+}
+
+// Streams a text VDF document, calling `visit` with the parent key path, key and value of every string
+pub fn visit_text<F: FnMut(&[String],&str,&str)>(contents: &str,mut visit: F) -> error::Result<()> {
+    let mut tokens = TextTokens::new(contents);
+    let mut path: Vec<String> = Vec::new();
+    let mut key: Option<String> = None;
+
+    while let Some(token) = tokens.next_token()? {
+        match (token,key.take()) {
+            (TextToken::Str(val),Some(k)) => visit(&path,&k,&val),
+            (TextToken::Str(k),None) => key = Some(k),
+            (TextToken::Open,Some(k)) => path.push(k),
+            (TextToken::Close,None) if path.pop().is_some() => {},
+            (_,_) => return Err(error::err(ErrorCode::ParseFailed,format!("Unexpected token at offset {}",tokens.pos)))
+        }
+    }
+
+    match (path.last(),key) {
+        (None,None) => Ok(()),
+        _ => Err(error::err(ErrorCode::ParseFailed,"Unexpected end of file"))
+    }
+}
+
+pub fn query(path: &Path,key_path: &str) -> error::Result<VdfValue> {
+    load(path)?
+        .get_path(key_path)
+        .cloned()
+        .ok_or_else(|| error::err(ErrorCode::KeyNotFound,format!("\"{}\" not found in \"{}\"",key_path,path.display())))
+}
+
+#[napi]
+pub fn query_vdf(path: String,key_path: String) -> error::Result<JsonValue> {
+    query(Path::new(&path),&key_path).map(|value| value.to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+    use serde_json::json;
+
+    #[test]
+    fn repeated_keys_become_arrays() {
+        let value = load(&fixture("sample.vdf")).unwrap();
+
+        assert_eq!(value.to_json(),json!({
+            "Root": {
+                "Name": "Sample",
+                "Tag": ["one","two","three"],
+                "Nested": { "Value": "42" }
+            }
+        }));
+    }
+
+    #[test]
+    fn binary_values_to_json() {
+        let value = VdfValue::Obj(vec![
+            ("id".to_string(),VdfValue::UInt64(76561197960287930)),
+            ("count".to_string(),VdfValue::Int(3)),
+            ("count".to_string(),VdfValue::Int(4))
+        ]);
+
+        assert_eq!(value.to_json(),json!({ "id": "76561197960287930", "count": [3,4] }));
+    }
+
+    #[test]
+    fn queries_paths_case_insensitively() {
+        let path = fixture("sample.vdf");
+
+        assert_eq!(query(&path,"root/nested/VALUE").unwrap(),VdfValue::Str("42".to_string()));
+        assert_eq!(query(&path,"/Root//Name/").unwrap(),VdfValue::Str("Sample".to_string()));
+        assert_eq!(query(&path,"ROOT/nested").unwrap().get_i64("value"),Some(42));
+    }
+
+    fn visit_all(contents: &str) -> error::Result<Vec<(String,String,String)>> {
+        let mut visited = Vec::new();
+        visit_text(contents,|path,key,val| visited.push((path.join("/"),key.to_string(),val.to_string())))?;
+        Ok(visited)
+    }
+
+    #[test]
+    fn streams_text_tokens() {
+        let contents = r#"
+            // Leading comment
+            "root"
+            {
+                "quoted"    "a \"b\" c\\d\ne\tf"
+                unquoted    value   // trailing comment
+                "cond"      "1"     [$WIN32]
+                "nested" { "key" "val" }
+                "empty"     ""
+            }
+        "#;
+
+        assert_eq!(visit_all(contents).unwrap(),[
+            ("root".to_string(),"quoted".to_string(),"a \"b\" c\\d\ne\tf".to_string()),
+            ("root".to_string(),"unquoted".to_string(),"value".to_string()),
+            ("root".to_string(),"cond".to_string(),"1".to_string()),
+            ("root/nested".to_string(),"key".to_string(),"val".to_string()),
+            ("root".to_string(),"empty".to_string(),"".to_string())
+        ]);
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        for contents in ["\"root\" {","\"root\" { } }","\"root\" { \"key\" }","{ }","\"root\" { \"key\" \"unterminated }"] {
+            assert_eq!(visit_all(contents).unwrap_err().status,ErrorCode::ParseFailed,"{}",contents);
+        }
+    }
+
+    #[test]
+    fn typed_query_errors() {
+        assert_eq!(query(&fixture("missing.vdf"),"Root").unwrap_err().status,ErrorCode::FileNotFound);
+        assert_eq!(query(&fixture("malformed.vdf"),"Root").unwrap_err().status,ErrorCode::ParseFailed);
+        assert_eq!(query(&fixture("sample.vdf"),"Root/Nested/Missing").unwrap_err().status,ErrorCode::KeyNotFound);
+        assert_eq!(query(&fixture("sample.vdf"),"Root/Name/Value").unwrap_err().status,ErrorCode::KeyNotFound);
+    }
+}
@@ -0,0 +1,324 @@
+use napi_derive::napi;
+use napi::{JsFunction,threadsafe_function::{ThreadsafeFunction,ErrorStrategy,ThreadsafeFunctionCallMode}};
+use std::{collections::HashMap,fs,path::PathBuf,sync::mpsc::{self,Receiver,Sender,RecvTimeoutError},thread::{self,JoinHandle},time::{Duration,SystemTime,UNIX_EPOCH}};
+use extern_log::{info,error};
+use crate::{error::{self,ErrorCode},library,stats::{self,AchievementState}};
+
+const DEFAULT_INTERVAL_MS: u32 = 1000;
+
+#[napi(object)]
+#[derive(Clone,Debug)]
+pub struct UnlockEvent {
+    pub appid: u32,
+    pub apiname: String,
+    pub unlocktime: u32
+}
+
+type UnlockCallback = ThreadsafeFunction<UnlockEvent,ErrorStrategy::Fatal>;
+
+pub fn parse_stats_filename(filename: &str) -> Option<(u32,u32)> {
+    let (accountid,appid) = filename
+        .strip_prefix("UserGameStats_")?
+        .strip_suffix(".bin")?
+        .split_once('_')?;
+
+    Some((accountid.parse().ok()?,appid.parse().ok()?))
+}
+
+// Unlocks in `new` that were locked (or absent) in `old`, oldest first
+pub fn new_unlocks(appid: u32,old: &[AchievementState],new: &[AchievementState]) -> Vec<UnlockEvent> {
+    let mut events: Vec<UnlockEvent> = new
+        .iter()
+        .filter(|achievement| achievement.unlocked)
+        .filter(|achievement| !old
+            .iter()
+            .any(|prev| prev.apiname == achievement.apiname && prev.unlocked)
+        )
+        .map(|achievement| UnlockEvent {
+            appid,
+            apiname: achievement.apiname.clone(),
+            unlocktime: achievement.unlocktime
+        })
+        .collect();
+
+    events.sort_by_key(|event| event.unlocktime);
+    events
+}
+
+struct WatchState {
+    steam_path: PathBuf,
+    accountid: Option<u32>,
+    started: u32,
+    known: HashMap<(u32,u32),Vec<AchievementState>>,
+    mtimes: HashMap<PathBuf,SystemTime>
+}
+
+impl WatchState {
+    fn new(steam_path: PathBuf,accountid: Option<u32>) -> Self {
+        WatchState {
+            steam_path,
+            accountid,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs() as u32)
+                .unwrap_or(0),
+            known: HashMap::new(),
+            mtimes: HashMap::new()
+        }
+    }
+
+    fn stats_dir(&self) -> PathBuf {
+        stats::stats_dir(&self.steam_path)
+    }
+
+    fn matches(&self,filename: &str) -> Option<(u32,u32)> {
+        parse_stats_filename(filename).filter(|(accountid,_)| self.accountid.is_none_or(|id| id == *accountid))
+    }
+
+    fn stats_files(&self) -> Vec<(PathBuf,(u32,u32))> {
+        fs::read_dir(self.stats_dir())
+            .map(|entries| entries
+                .flatten()
+                .filter_map(|entry| self
+                    .matches(&entry.file_name().to_string_lossy())
+                    .map(|ids| (entry.path(),ids))
+                )
+                .collect()
+            )
+            .unwrap_or_default()
+    }
+
+    fn states(&self,accountid: u32,appid: u32) -> Option<Vec<AchievementState>> {
+        match stats::user_game_stats(&self.steam_path,appid,accountid) {
+            Ok(stats) => Some(stats.achievements),
+            Err(err) => {
+                error!("Failed to read achievement states for {} ({}): {}",appid,accountid,err);
+                None
+            }
+        }
+    }
+
+    // Records the current unlock state of every stats file, without reporting anything
+    fn baseline(&mut self) {
+        for (path,(accountid,appid)) in self.stats_files() {
+            if let Ok(mtime) = fs::metadata(&path).and_then(|meta| meta.modified()) {
+                self.mtimes.insert(path,mtime);
+            }
+
+            if let Some(states) = self.states(accountid,appid) {
+                self.known.insert((accountid,appid),states);
+            }
+        }
+
+        info!("Watching {} stats file(s) in \"{}\"",self.known.len(),self.stats_dir().display());
+    }
+
+    fn refresh(&mut self,accountid: u32,appid: u32) -> Vec<UnlockEvent> {
+        let states = match self.states(accountid,appid) {
+            Some(states) => states,
+            None => return Vec::new()
+        };
+
+        let events = match self.known.get(&(accountid,appid)) {
+            Some(old) => new_unlocks(appid,old,&states),
+            // A file created after starting may also hold older unlocks synced from another machine
+            None => new_unlocks(appid,&[],&states)
+                .into_iter()
+                .filter(|event| event.unlocktime >= self.started)
+                .collect()
+        };
+
+        self.known.insert((accountid,appid),states);
+        events
+    }
+
+    fn poll(&mut self) -> Vec<UnlockEvent> {
+        let mut events = Vec::new();
+
+        for (path,(accountid,appid)) in self.stats_files() {
+            let mtime = match fs::metadata(&path).and_then(|meta| meta.modified()) {
+                Ok(mtime) => mtime,
+                Err(_) => continue
+            };
+
+            if self.mtimes.get(&path) != Some(&mtime) {
+                self.mtimes.insert(path,mtime);
+                events.extend(self.refresh(accountid,appid));
+            }
+        }
+
+        events
+    }
+}
+
+fn emit(callback: &UnlockCallback,events: Vec<UnlockEvent>) {
+    for event in events {
+        info!("Achievement \"{}\" unlocked in {}",event.apiname,event.appid);
+        callback.call(event,ThreadsafeFunctionCallMode::NonBlocking);
+    }
+}
+
+#[cfg(target_os="linux")]
+fn run_inotify(state: &mut WatchState,callback: &UnlockCallback,stop: &Receiver<()>) -> std::io::Result<()> {
+    use inotify::{Inotify,WatchMask};
+
+    const TICK: Duration = Duration::from_millis(100);
+
+    let mut inotify = Inotify::init()?;
+    inotify.watches().add(state.stats_dir(),WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO)?;
+
+    let mut buffer = [0u8;4096];
+
+    loop {
+        match inotify.read_events(&mut buffer) {
+            Ok(events) => {
+                let mut changed: Vec<(u32,u32)> = events
+                    .filter_map(|event| event.name.and_then(|name| state.matches(&name.to_string_lossy())))
+                    .collect();
+
+                changed.sort_unstable();
+                changed.dedup();
+
+                for (accountid,appid) in changed {
+                    emit(callback,state.refresh(accountid,appid));
+                }
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {},
+            Err(err) => return Err(err)
+        }
+
+        match stop.recv_timeout(TICK) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            _ => return Ok(())
+        }
+    }
+}
+
+fn run(mut state: WatchState,callback: UnlockCallback,stop: Receiver<()>,interval: Duration) {
+    state.baseline();
+
+    #[cfg(target_os="linux")] {
+        match run_inotify(&mut state,&callback,&stop) {
+            Ok(_) => return,
+            Err(err) => error!("Failed to watch \"{}\" with inotify, falling back to polling: {}",state.stats_dir().display(),err)
+        }
+    }
+
+    loop {
+        emit(&callback,state.poll());
+
+        match stop.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            _ => return
+        }
+    }
+}
+
+/// Watches `appcache/stats` and calls back with `{ appid, apiname, unlocktime }` for every new unlock.
+/// Uses inotify on Linux, otherwise polls file modification times every `intervalMs`
+#[napi]
+pub struct AchievementWatcher {
+    accountid: Option<u32>,
+    interval: Duration,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>
+}
+
+#[napi]
+impl AchievementWatcher {
+    #[napi(constructor)]
+    pub fn new(accountid: Option<u32>,interval_ms: Option<u32>) -> Self {
+        AchievementWatcher {
+            accountid,
+            interval: Duration::from_millis(interval_ms.unwrap_or(DEFAULT_INTERVAL_MS) as u64),
+            stop: None,
+            thread: None
+        }
+    }
+
+    #[napi(ts_args_type = "callback: (event: UnlockEvent) => void")]
+    pub fn start(&mut self,callback: JsFunction) -> error::Result<()> {
+        if self.running() {
+            return Err(error::err(ErrorCode::WatchFailed,"Watcher is already running"))
+        }
+
+        let state = WatchState::new(library::steam_root()?,self.accountid);
+        let callback: UnlockCallback = callback
+            .create_threadsafe_function(0,|ctx| Ok(vec![ctx.value]))
+            .map_err(|err| error::err(ErrorCode::WatchFailed,format!("Failed to create callback: {}",err)))?;
+
+        let (tx,rx) = mpsc::channel();
+        let interval = self.interval;
+
+        self.thread = Some(thread::spawn(move || run(state,callback,rx,interval)));
+        self.stop = Some(tx);
+        Ok(())
+    }
+
+    #[napi]
+    pub fn stop(&mut self) {
+        if let Some(tx) = self.stop.take() {
+            let _ = tx.send(());
+        }
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                error!("Achievement watcher thread panicked");
+            }
+        }
+    }
+
+    #[napi(getter)]
+    pub fn running(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_some_and(|thread| !thread.is_finished())
+    }
+}
+
+impl Drop for AchievementWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(apiname: &str,unlocktime: u32) -> AchievementState {
+        AchievementState {
+            apiname: apiname.to_string(),
+            unlocked: unlocktime != 0,
+            unlocktime
+        }
+    }
+
+    #[test]
+    fn parses_stats_filenames() {
+        assert_eq!(parse_stats_filename("UserGameStats_12345_440.bin"),Some((12345,440)));
+        assert_eq!(parse_stats_filename("UserGameStatsSchema_440.bin"),None);
+        assert_eq!(parse_stats_filename("UserGameStats_12345_440.bin.tmp"),None);
+        assert_eq!(parse_stats_filename("UserGameStats_12345.bin"),None);
+        assert_eq!(parse_stats_filename("UserGameStats_abc_440.bin"),None);
+    }
+
+    #[test]
+    fn reports_new_unlocks_oldest_first() {
+        let old = [state("A",100),state("B",0),state("C",0)];
+        let new = [state("A",100),state("B",300),state("C",200),state("D",250)];
+
+        let events: Vec<(String,u32)> = new_unlocks(440,&old,&new)
+            .into_iter()
+            .map(|event| (event.apiname,event.unlocktime))
+            .collect();
+
+        assert_eq!(events,[("C".to_string(),200),("D".to_string(),250),("B".to_string(),300)]);
+    }
+
+    #[test]
+    fn ignores_relocked_achievements() {
+        assert!(new_unlocks(440,&[state("A",100)],&[state("A",0)]).is_empty());
+        assert!(new_unlocks(440,&[state("A",100)],&[state("A",100)]).is_empty());
+    }
+}
@@ -0,0 +1,611 @@
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use image::RgbaImage;
+use crate::{encode::{self,EncodeOptions,ImageFormat},error::{self,ErrorCode}};
+
+/// Every field given must match
+#[napi(object)]
+#[derive(Clone,Debug,Default)]
+pub struct WindowQuery {
+    /// Exact window title
+    pub title: Option<String>,
+    pub pid: Option<u32>,
+    /// Executable of the process owning the window
+    pub process_path: Option<String>
+}
+
+#[napi(object)]
+pub struct WindowCapture {
+    pub data: Buffer,
+    /// `png`, `jpeg` or `webp`
+    pub format: String,
+    pub width: u32,
+    pub height: u32,
+    /// Desktop position of the captured area
+    pub x: i32,
+    pub y: i32,
+    /// `xcomposite` or `printwindow` (the window's own contents, even when occluded) or `crop` (the desktop area the window covers)
+    pub method: String
+}
+
+pub struct CapturedWindow {
+    pub img: RgbaImage,
+    pub x: i32,
+    pub y: i32,
+    pub method: &'static str
+}
+
+impl WindowQuery {
+    fn validate(&self) -> error::Result<()> {
+        if self.title.is_none() && self.pid.is_none() && self.process_path.is_none() {
+            return Err(error::err(ErrorCode::InvalidArgument,"One of \"title\", \"pid\" or \"processPath\" is required"))
+        }
+
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = Vec::new();
+
+        if let Some(title) = &self.title {
+            parts.push(format!("title \"{}\"",title));
+        }
+
+        if let Some(pid) = self.pid {
+            parts.push(format!("pid {}",pid));
+        }
+
+        if let Some(path) = &self.process_path {
+            parts.push(format!("process \"{}\"",path));
+        }
+
+        parts.join(", ")
+    }
+}
+
+#[cfg(target_os="linux")]
+mod x11_capture {
+    use std::{ffi::{CStr,CString},os::raw::{c_int,c_uchar,c_ulong},path::Path,ptr,slice,sync::atomic::{AtomicBool,Ordering}};
+    use image::{Rgba,RgbaImage};
+    use x11::xlib::*;
+    use super::{CapturedWindow,WindowQuery};
+    use crate::{error::{self,ErrorCode},procscan::ProcScanner};
+
+    #[link(name = "Xcomposite")]
+    extern "C" {
+        fn XCompositeQueryExtension(display: *mut Display,event_base: *mut c_int,error_base: *mut c_int) -> c_int;
+        fn XCompositeNameWindowPixmap(display: *mut Display,window: Window) -> Pixmap;
+    }
+
+    type ErrorHandler = Option<unsafe extern "C" fn(*mut Display,*mut XErrorEvent) -> c_int>;
+
+    // Xlib's default handler exits the process, e.g. on `BadMatch` when naming the pixmap of an unredirected window
+    static X_ERROR: AtomicBool = AtomicBool::new(false);
+
+    unsafe extern "C" fn on_error(_display: *mut Display,_event: *mut XErrorEvent) -> c_int {
+        X_ERROR.store(true,Ordering::SeqCst);
+        0
+    }
+
+    // Restores the previous error handler and closes the display however capture ends
+    struct Connection {
+        display: *mut Display,
+        previous: ErrorHandler
+    }
+
+    impl Connection {
+        unsafe fn open() -> Option<Self> {
+            let display = XOpenDisplay(ptr::null());
+
+            if display.is_null() {
+                return None
+            }
+
+            Some(Connection {
+                display,
+                previous: XSetErrorHandler(Some(on_error))
+            })
+        }
+
+        // Flushes pending requests, returning whether any of them failed
+        unsafe fn failed(&self) -> bool {
+            XSync(self.display,False);
+            X_ERROR.swap(false,Ordering::SeqCst)
+        }
+
+        unsafe fn atom(&self,name: &str) -> Atom {
+            let name = CString::new(name).expect("atom names have no NUL bytes");
+            XInternAtom(self.display,name.as_ptr(),False)
+        }
+
+        // 32-bit items (windows, cardinals) or raw bytes of a format 8 property
+        unsafe fn property(&self,window: Window,property: Atom,req_type: Atom) -> Option<(c_int,Vec<c_ulong>,Vec<u8>)> {
+            let mut actual_type = 0;
+            let mut format = 0;
+            let mut nitems = 0;
+            let mut bytes_after = 0;
+            let mut data: *mut c_uchar = ptr::null_mut();
+
+            let status = XGetWindowProperty(self.display,window,property,0,4096,False,req_type,&mut actual_type,&mut format,&mut nitems,&mut bytes_after,&mut data);
+
+            if status != Success as c_int || data.is_null() {
+                return None
+            }
+
+            let result = match format {
+                32 => Some((format,slice::from_raw_parts(data as *const c_ulong,nitems as usize).to_vec(),Vec::new())),
+                8 => Some((format,Vec::new(),slice::from_raw_parts(data,nitems as usize).to_vec())),
+                _ => None
+            };
+
+            XFree(data as *mut _);
+            result
+        }
+
+        unsafe fn title(&self,window: Window) -> Option<String> {
+            let utf8 = self
+                .property(window,self.atom("_NET_WM_NAME"),self.atom("UTF8_STRING"))
+                .map(|(_,_,bytes)| String::from_utf8_lossy(&bytes).into_owned())
+                .filter(|title| !title.is_empty());
+
+            utf8.or_else(|| {
+                let mut name = ptr::null_mut();
+
+                if XFetchName(self.display,window,&mut name) == 0 || name.is_null() {
+                    return None
+                }
+
+                let title = CStr::from_ptr(name).to_string_lossy().into_owned();
+                XFree(name as *mut _);
+                Some(title)
+            })
+        }
+
+        unsafe fn pid(&self,window: Window) -> Option<u32> {
+            self.property(window,self.atom("_NET_WM_PID"),XA_CARDINAL)
+                .and_then(|(_,items,_)| items.first().map(|&pid| pid as u32))
+        }
+
+        unsafe fn children(&self,window: Window) -> Vec<Window> {
+            let mut root = 0;
+            let mut parent = 0;
+            let mut children: *mut Window = ptr::null_mut();
+            let mut count = 0;
+
+            if XQueryTree(self.display,window,&mut root,&mut parent,&mut children,&mut count) == 0 || children.is_null() {
+                return Vec::new()
+            }
+
+            let list = slice::from_raw_parts(children,count as usize).to_vec();
+            XFree(children as *mut _);
+            list
+        }
+
+        unsafe fn parent(&self,window: Window) -> Option<Window> {
+            let mut root = 0;
+            let mut parent = 0;
+            let mut children: *mut Window = ptr::null_mut();
+            let mut count = 0;
+
+            if XQueryTree(self.display,window,&mut root,&mut parent,&mut children,&mut count) == 0 {
+                return None
+            }
+
+            if !children.is_null() {
+                XFree(children as *mut _);
+            }
+
+            Some(parent)
+        }
+
+        // Windows managed by the window manager, or every window when it does not publish `_NET_CLIENT_LIST`
+        unsafe fn client_windows(&self,root: Window) -> Vec<Window> {
+            if let Some((_,clients,_)) = self.property(root,self.atom("_NET_CLIENT_LIST"),XA_WINDOW) {
+                if !clients.is_empty() {
+                    return clients
+                }
+            }
+
+            let mut windows = Vec::new();
+            let mut pending = self.children(root);
+
+            while let Some(window) = pending.pop() {
+                pending.extend(self.children(window));
+                windows.push(window);
+            }
+
+            windows
+        }
+
+        unsafe fn is_viewable(&self,window: Window) -> bool {
+            let mut attrs: XWindowAttributes = std::mem::zeroed();
+            XGetWindowAttributes(self.display,window,&mut attrs) != 0 && attrs.map_state == IsViewable
+        }
+
+        unsafe fn find_window(&self,root: Window,query: &WindowQuery) -> Option<Window> {
+            let pids: Option<Vec<u32>> = match (&query.process_path,query.pid) {
+                (Some(path),pid) => Some(ProcScanner::default()
+                    .pids_for_exe(Path::new(path))
+                    .into_iter()
+                    .filter(|found| pid.is_none_or(|pid| pid == *found))
+                    .collect()
+                ),
+                (None,Some(pid)) => Some(vec![pid]),
+                (None,None) => None
+            };
+
+            let matches = |window: Window| {
+                pids.as_ref().is_none_or(|pids| self.pid(window).is_some_and(|pid| pids.contains(&pid)))
+                    && query.title.as_ref().is_none_or(|title| self.title(window).as_ref() == Some(title))
+            };
+
+            let candidates: Vec<Window> = self
+                .client_windows(root)
+                .into_iter()
+                .filter(|&window| matches(window))
+                .collect();
+
+            // Prefer a mapped window over hidden helpers of the same process
+            candidates
+                .iter()
+                .find(|&&window| self.is_viewable(window))
+                .or(candidates.first())
+                .copied()
+        }
+
+        unsafe fn size(&self,window: Window) -> Option<(u32,u32)> {
+            let mut attrs: XWindowAttributes = std::mem::zeroed();
+
+            if XGetWindowAttributes(self.display,window,&mut attrs) == 0 {
+                return None
+            }
+
+            Some((attrs.width as u32,attrs.height as u32))
+        }
+
+        unsafe fn translate(&self,window: Window,to: Window) -> Option<(i32,i32)> {
+            let mut x = 0;
+            let mut y = 0;
+            let mut child = 0;
+
+            if XTranslateCoordinates(self.display,window,to,0,0,&mut x,&mut y,&mut child) == 0 {
+                return None
+            }
+
+            Some((x,y))
+        }
+
+        // The child of the root holding `window`, i.e. its window manager frame
+        unsafe fn top_level(&self,root: Window,window: Window) -> Window {
+            let mut current = window;
+
+            while let Some(parent) = self.parent(current) {
+                if parent == root || parent == 0 {
+                    break
+                }
+
+                current = parent;
+            }
+
+            current
+        }
+
+        // A compositing manager owns this selection while it runs
+        unsafe fn compositor_running(&self) -> bool {
+            let selection = self.atom(&format!("_NET_WM_CM_S{}",XDefaultScreen(self.display)));
+            XGetSelectionOwner(self.display,selection) != 0
+        }
+
+        // A running compositor keeps the frame's off-screen pixmap painted, holding the window in full wherever it is and whatever covers it.
+        // The frame is never redirected here: a freshly redirected pixmap is unpainted until the client repaints, and redirecting a
+        // fullscreen game the compositor has unredirected would make it flip out of direct scanout and stutter
+        unsafe fn composite_image(&self,frame: Window,x: i32,y: i32,width: u32,height: u32) -> Option<RgbaImage> {
+            // Drop errors left over from the window lookup, so only the requests below are checked
+            self.failed();
+
+            // `BadMatch` when the frame is not redirected, e.g. a fullscreen game, which is then cropped instead
+            let pixmap = XCompositeNameWindowPixmap(self.display,frame);
+
+            if self.failed() || pixmap == 0 {
+                return None
+            }
+
+            let img = self.get_image(pixmap,x,y,width,height);
+            XFreePixmap(self.display,pixmap);
+            img
+        }
+
+        unsafe fn get_image(&self,drawable: Drawable,x: i32,y: i32,width: u32,height: u32) -> Option<RgbaImage> {
+            let image = XGetImage(self.display,drawable,x,y,width,height,XAllPlanes(),ZPixmap);
+
+            if self.failed() || image.is_null() {
+                return None
+            }
+
+            let rgba = to_rgba(image);
+            XDestroyImage(image);
+            Some(rgba)
+        }
+    }
+
+    impl Drop for Connection {
+        fn drop(&mut self) {
+            unsafe {
+                XSetErrorHandler(self.previous);
+                XCloseDisplay(self.display);
+            }
+        }
+    }
+
+    fn channel(pixel: c_ulong,mask: c_ulong) -> u8 {
+        if mask == 0 {
+            return 0
+        }
+
+        let shift = mask.trailing_zeros();
+        (((pixel & mask) >> shift) * 255 / (mask >> shift)) as u8
+    }
+
+    // Window contents are opaque, so any alpha in a 32-bit visual is ignored
+    unsafe fn to_rgba(image: *mut XImage) -> RgbaImage {
+        let ximage = &*image;
+        let (red,green,blue) = (ximage.red_mask,ximage.green_mask,ximage.blue_mask);
+
+        if ximage.bits_per_pixel == 32 {
+            let stride = ximage.bytes_per_line as usize;
+            let data = slice::from_raw_parts(ximage.data as *const u8,stride * ximage.height as usize);
+            let lsb = ximage.byte_order == LSBFirst;
+
+            return RgbaImage::from_fn(ximage.width as u32,ximage.height as u32,|x,y| {
+                let offset = y as usize * stride + x as usize * 4;
+                let bytes = [data[offset],data[offset + 1],data[offset + 2],data[offset + 3]];
+                let pixel = if lsb { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) } as c_ulong;
+
+                Rgba([channel(pixel,red),channel(pixel,green),channel(pixel,blue),255])
+            })
+        }
+
+        RgbaImage::from_fn(ximage.width as u32,ximage.height as u32,|x,y| {
+            let pixel = XGetPixel(image,x as c_int,y as c_int);
+            Rgba([channel(pixel,red),channel(pixel,green),channel(pixel,blue),255])
+        })
+    }
+
+    pub fn capture(query: &WindowQuery) -> error::Result<CapturedWindow> {
+        unsafe {
+            let conn = Connection::open()
+                .ok_or_else(|| error::err(ErrorCode::CaptureFailed,"Failed to open X display"))?;
+
+            let root = XDefaultRootWindow(conn.display);
+            let window = conn
+                .find_window(root,query)
+                .ok_or_else(|| error::err(ErrorCode::WindowNotFound,format!("No window matching {}",query.describe())))?;
+
+            let (width,height) = conn.size(window)
+                .ok_or_else(|| error::err(ErrorCode::WindowNotFound,format!("Failed to get the geometry of window 0x{:x}",window)))?;
+            let (x,y) = conn.translate(window,root)
+                .ok_or_else(|| error::err(ErrorCode::WindowNotFound,format!("Failed to get the position of window 0x{:x}",window)))?;
+
+            let (mut event_base,mut error_base) = (0,0);
+
+            if XCompositeQueryExtension(conn.display,&mut event_base,&mut error_base) != 0 && conn.compositor_running() {
+                let frame = conn.top_level(root,window);
+                let img = conn
+                    .translate(window,frame)
+                    .and_then(|(offset_x,offset_y)| conn.composite_image(frame,offset_x,offset_y,width,height));
+
+                if let Some(img) = img {
+                    return Ok(CapturedWindow { img, x, y, method: "xcomposite" })
+                }
+            }
+
+            // Otherwise crop what is on screen, clipped to the root window
+            let (root_width,root_height) = conn.size(root).unwrap_or((0,0));
+            let left = x.max(0);
+            let top = y.max(0);
+            let right = (x + width as i32).min(root_width as i32);
+            let bottom = (y + height as i32).min(root_height as i32);
+
+            if right <= left || bottom <= top {
+                return Err(error::err(ErrorCode::CaptureFailed,format!("Window 0x{:x} is entirely off-screen",window)))
+            }
+
+            let img = conn
+                .get_image(root,left,top,(right - left) as u32,(bottom - top) as u32)
+                .ok_or_else(|| error::err(ErrorCode::CaptureFailed,format!("Failed to read the screen area of window 0x{:x}",window)))?;
+
+            Ok(CapturedWindow { img, x: left, y: top, method: "crop" })
+        }
+    }
+}
+
+#[cfg(target_os="windows")]
+mod win32_windows {
+    use std::path::{Path,PathBuf};
+    use windows::{
+        core::{BOOL,PWSTR},
+        Win32::{
+            Foundation::{CloseHandle,HWND,LPARAM,RECT},
+            Graphics::Gdi::{BI_RGB,BITMAPINFO,BITMAPINFOHEADER,CreateCompatibleBitmap,CreateCompatibleDC,DIB_RGB_COLORS,DeleteDC,DeleteObject,GetDC,GetDIBits,ReleaseDC,SelectObject},
+            Storage::Xps::{PrintWindow,PRINT_WINDOW_FLAGS},
+            System::Threading::{OpenProcess,QueryFullProcessImageNameW,PROCESS_NAME_WIN32,PROCESS_QUERY_LIMITED_INFORMATION},
+            UI::WindowsAndMessaging::{EnumWindows,GetWindowRect,GetWindowTextW,GetWindowThreadProcessId,IsWindowVisible}
+        }
+    };
+    use image::RgbaImage;
+    use super::WindowQuery;
+
+    // Also renders DirectX and DirectComposition content, which plain `PrintWindow` leaves black. Missing from the metadata
+    const PW_RENDERFULLCONTENT: PRINT_WINDOW_FLAGS = PRINT_WINDOW_FLAGS(2);
+
+    unsafe extern "system" fn collect(hwnd: HWND,windows: LPARAM) -> BOOL {
+        (*(windows.0 as *mut Vec<HWND>)).push(hwnd);
+        true.into()
+    }
+
+    unsafe fn top_level_windows() -> Vec<HWND> {
+        let mut windows: Vec<HWND> = Vec::new();
+        let _ = EnumWindows(Some(collect),LPARAM(&mut windows as *mut Vec<HWND> as isize));
+        windows
+    }
+
+    unsafe fn pid(hwnd: HWND) -> u32 {
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd,Some(&mut pid));
+        pid
+    }
+
+    unsafe fn title(hwnd: HWND) -> String {
+        let mut buf = [0u16; 512];
+        let len = GetWindowTextW(hwnd,&mut buf).max(0) as usize;
+        String::from_utf16_lossy(&buf[..len])
+    }
+
+    unsafe fn process_path(pid: u32) -> Option<PathBuf> {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION,false,pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+
+        let result = QueryFullProcessImageNameW(process,PROCESS_NAME_WIN32,PWSTR(buf.as_mut_ptr()),&mut len);
+        let _ = CloseHandle(process);
+        result.ok()?;
+
+        Some(PathBuf::from(String::from_utf16_lossy(&buf[..len as usize])))
+    }
+
+    // Handle and left/top/width/height of the first visible top-level window matching every field given
+    pub fn find_window(query: &WindowQuery) -> Option<(HWND,i32,i32,u32,u32)> {
+        unsafe {
+            top_level_windows()
+                .into_iter()
+                .filter(|&hwnd| IsWindowVisible(hwnd).as_bool())
+                .filter(|&hwnd| {
+                    let pid = pid(hwnd);
+
+                    query.pid.is_none_or(|wanted| wanted == pid)
+                        && query.process_path.as_ref().is_none_or(|path| process_path(pid).is_some_and(|found| found == Path::new(path)))
+                        && query.title.as_ref().is_none_or(|wanted| *wanted == title(hwnd))
+                })
+                .find_map(|hwnd| {
+                    let mut rect = RECT::default();
+                    GetWindowRect(hwnd,&mut rect).ok()?;
+                    Some((hwnd,rect.left,rect.top,(rect.right - rect.left) as u32,(rect.bottom - rect.top) as u32))
+                })
+        }
+    }
+
+    // Has the window draw itself into a bitmap, so it is captured even when covered or partly off-screen
+    pub fn print_window(hwnd: HWND,width: u32,height: u32) -> Option<RgbaImage> {
+        if width == 0 || height == 0 {
+            return None
+        }
+
+        unsafe {
+            let screen = GetDC(None);
+            let dc = CreateCompatibleDC(Some(screen));
+            let bitmap = CreateCompatibleBitmap(screen,width as i32,height as i32);
+            let previous = SelectObject(dc,bitmap.into());
+
+            let printed = PrintWindow(hwnd,dc,PW_RENDERFULLCONTENT).as_bool();
+
+            // `GetDIBits` needs the bitmap deselected first
+            SelectObject(dc,previous);
+
+            let mut info = BITMAPINFO {
+                bmiHeader: BITMAPINFOHEADER {
+                    biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                    biWidth: width as i32,
+                    // Negative for top-down rows
+                    biHeight: -(height as i32),
+                    biPlanes: 1,
+                    biBitCount: 32,
+                    biCompression: BI_RGB.0,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut pixels = vec![0u8; width as usize * height as usize * 4];
+
+            let rows = match printed {
+                true => GetDIBits(dc,bitmap,0,height,Some(pixels.as_mut_ptr() as *mut _),&mut info,DIB_RGB_COLORS),
+                false => 0
+            };
+
+            let _ = DeleteObject(bitmap.into());
+            let _ = DeleteDC(dc);
+            ReleaseDC(None,screen);
+
+            if rows != height as i32 {
+                return None
+            }
+
+            // BGRA, and GDI leaves alpha undefined
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0,2);
+                pixel[3] = 255;
+            }
+
+            RgbaImage::from_raw(width,height,pixels)
+        }
+    }
+}
+
+// Windows falls back to cropping the monitor under the window's centre, so the window then has to be visible on screen
+#[cfg(target_os="windows")]
+fn capture_window_image(query: &WindowQuery) -> error::Result<CapturedWindow> {
+    let (hwnd,x,y,width,height) = win32_windows::find_window(query)
+        .ok_or_else(|| error::err(ErrorCode::WindowNotFound,format!("No window matching {}",query.describe())))?;
+
+    if let Some(img) = win32_windows::print_window(hwnd,width,height) {
+        return Ok(CapturedWindow { img, x, y, method: "printwindow" })
+    }
+
+    let screen = screenshots::Screen::from_point(x + width as i32 / 2,y + height as i32 / 2)
+        .map_err(|err| error::err(ErrorCode::MonitorNotFound,format!("No monitor under the window: {}",err)))?;
+
+    let info = &screen.display_info;
+    let left = x.max(info.x);
+    let top = y.max(info.y);
+    let right = (x + width as i32).min(info.x + info.width as i32);
+    let bottom = (y + height as i32).min(info.y + info.height as i32);
+
+    if right <= left || bottom <= top {
+        return Err(error::err(ErrorCode::CaptureFailed,"Window is entirely off-screen"))
+    }
+
+    let img = crate::capture::capture(&screen,Some(((top - info.y) as u32,(left - info.x) as u32,(right - left) as u32,(bottom - top) as u32)))?;
+
+    Ok(CapturedWindow { img, x: left, y: top, method: "crop" })
+}
+
+#[cfg(target_os="linux")]
+fn capture_window_image(query: &WindowQuery) -> error::Result<CapturedWindow> {
+    x11_capture::capture(query)
+}
+
+#[cfg(not(any(target_os="windows",target_os="linux")))]
+fn capture_window_image(_query: &WindowQuery) -> error::Result<CapturedWindow> {
+    Err(error::err(ErrorCode::CaptureFailed,"Unsupported OS"))
+}
+
+pub fn window_image(query: &WindowQuery) -> error::Result<CapturedWindow> {
+    query.validate()?;
+    capture_window_image(query)
+}
+
+#[napi]
+pub fn capture_window(query: WindowQuery,options: Option<EncodeOptions>) -> error::Result<WindowCapture> {
+    let format = ImageFormat::from_options(&options.unwrap_or_default())?;
+    let captured = window_image(&query)?;
+    let (width,height) = captured.img.dimensions();
+
+    Ok(WindowCapture {
+        data: encode::encode(captured.img,format)?.into(),
+        format: format.name().to_string(),
+        width,
+        height,
+        x: captured.x,
+        y: captured.y,
+        method: captured.method.to_string()
+    })
+}